@@ -1,20 +1,217 @@
 use std::path::PathBuf;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use glob::Pattern;
 use magic_converter::ConverterService;
+use magic_converter::video::avchd::clip_info_for;
+use magic_converter::video::progress::format_duration;
+use magic_converter::video::trim::parse_timestamp;
+use magic_converter::video::{
+    probe, BatchOptions, BatchReport, CancellationToken, Conversion, ConversionOptions, ConversionPhase, ConvertError,
+    AnimationFormat, AudioCodec, AudioSettings, FileOutcome, ImageFormat, MediaInfo, MetadataTags, OutputFormat,
+    OutputTemplate, OverwritePolicy, Preset, PreviewOptions, ProgressEvent, ProgressEventCallback, Quality, StreamInfo,
+    StreamKind, StreamMode, ThumbnailOptions, ThumbnailTime, Thumbnails, TimeRange, VideoCodec, VideoSettings,
+};
+use std::io::{self, Write};
+use std::process;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Print ffmpeg's diagnostic output while converting
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// What to do when an output file already exists: skip, overwrite, rename or error
+    #[arg(long, global = true, value_name = "POLICY", default_value_t = OverwritePolicy::Error)]
+    if_exists: OverwritePolicy,
+
+    /// Output path relative to the output directory, e.g. "{date:%Y}/{date:%m}/{stem}"; tokens are {stem}, {ext},
+    /// {date}, {time}, {camera}, {maker}, {model_code}, {width}, {height} and {index}
+    #[arg(long, global = true, value_name = "TEMPLATE")]
+    output_name: Option<OutputTemplate>,
+
+    /// Output format: mp4, fmp4 (fragmented MP4), mkv, mov or webm; m4a, mp3, flac, wav or opus extract the audio
+    #[arg(long, global = true, value_name = "FORMAT", default_value_t = OutputFormat::Mp4)]
+    format: OutputFormat,
+
+    /// Normalize audio loudness to the EBU R128 target of -23 LUFS
+    #[arg(long, global = true)]
+    normalize_loudness: bool,
+
+    /// Convert only from this time on: seconds, or [hh:]mm:ss[.frac]
+    #[arg(long, global = true, value_name = "TIME", value_parser = parse_timestamp)]
+    start: Option<Duration>,
+
+    /// Convert only up to this time
+    #[arg(long, global = true, value_name = "TIME", value_parser = parse_timestamp)]
+    end: Option<Duration>,
+
+    /// Convert only this much, counted from --start
+    #[arg(long, global = true, value_name = "TIME", value_parser = parse_timestamp, conflicts_with = "end")]
+    duration: Option<Duration>,
+
+    /// Title to tag outputs with
+    #[arg(long, global = true)]
+    title: Option<String>,
+
+    /// Comment to tag outputs with
+    #[arg(long, global = true)]
+    comment: Option<String>,
+
+    /// Author to tag outputs with
+    #[arg(long, global = true)]
+    author: Option<String>,
+
+    #[command(flatten)]
+    encoding: EncodingArgs,
+
+    #[command(flatten)]
+    thumbnails: ThumbnailArgs,
+}
+
+#[derive(Args)]
+struct ThumbnailArgs {
+    /// Also save a poster frame next to every converted video
+    #[arg(long = "thumbnails", global = true)]
+    enabled: bool,
+
+    /// Where to take the poster frame: a percentage like 25%, seconds, or mm:ss (defaults to 10%)
+    #[arg(long, global = true, value_name = "TIME")]
+    thumbnail_at: Option<ThumbnailTime>,
+
+    /// Also save a contact sheet with a grid of this many frames
+    #[arg(long, global = true, value_name = "FRAMES")]
+    contact_sheet: Option<u32>,
+
+    /// Image format for thumbnails: jpeg, png or webp
+    #[arg(long, global = true, value_name = "FORMAT", default_value_t = ImageFormat::Jpeg)]
+    image_format: ImageFormat,
+
+    /// Scale thumbnails to this width
+    #[arg(long, global = true, value_name = "PIXELS")]
+    thumbnail_width: Option<u32>,
+}
+
+impl ThumbnailArgs {
+    // Asking for a particular frame or a contact sheet implies wanting thumbnails
+    fn requested(&self) -> bool {
+        self.enabled || self.thumbnail_at.is_some() || self.contact_sheet.is_some()
+    }
+
+    fn options(&self, overwrite: OverwritePolicy) -> ThumbnailOptions {
+        ThumbnailOptions {
+            format: self.image_format,
+            time: self.thumbnail_at.unwrap_or_default(),
+            width: self.thumbnail_width,
+            contact_sheet: self.contact_sheet,
+            overwrite,
+        }
+    }
+}
+
+#[derive(Args)]
+struct EncodingArgs {
+    /// Named settings to start from: archive-copy, share-720p or web-small
+    #[arg(long, global = true, value_name = "NAME")]
+    preset: Option<Preset>,
+
+    /// How streams are converted: auto (copy what the format's players accept, re-encode the rest), copy or transcode
+    #[arg(long, global = true, value_name = "MODE")]
+    streams: Option<StreamMode>,
+
+    /// Re-encode video with this codec: h264, h265, vp9, av1 or av1-svt
+    #[arg(long, global = true, value_name = "CODEC")]
+    video_codec: Option<VideoCodec>,
+
+    /// Constant video quality (lower is better and larger)
+    #[arg(long, global = true, conflicts_with = "video_bitrate")]
+    crf: Option<u8>,
+
+    /// Target video bitrate in kbit/s
+    #[arg(long, global = true, value_name = "KBPS")]
+    video_bitrate: Option<u32>,
+
+    /// Encoder speed preset, e.g. medium or slow for h264
+    #[arg(long, global = true, value_name = "PRESET")]
+    encoder_preset: Option<String>,
+
+    /// Scale taller video down to this height
+    #[arg(long, global = true, value_name = "PIXELS")]
+    max_height: Option<u32>,
+
+    /// Change the video frame rate
+    #[arg(long, global = true)]
+    fps: Option<f64>,
+
+    /// Re-encode audio with this codec: aac, opus or mp3
+    #[arg(long, global = true, value_name = "CODEC")]
+    audio_codec: Option<AudioCodec>,
+
+    /// Audio bitrate in kbit/s
+    #[arg(long, global = true, value_name = "KBPS")]
+    audio_bitrate: Option<u32>,
+}
+
+impl EncodingArgs {
+    // Individual settings refine the preset; any video or audio setting implies re-encoding that stream type
+    fn apply(self, options: &mut ConversionOptions) {
+        if let Some(preset) = self.preset {
+            options.apply_preset(preset);
+        }
+        if let Some(mode) = self.streams {
+            options.stream_mode = mode;
+        }
+
+        let video_requested = self.video_codec.is_some()
+            || self.crf.is_some()
+            || self.video_bitrate.is_some()
+            || self.encoder_preset.is_some()
+            || self.max_height.is_some()
+            || self.fps.is_some();
+        if video_requested {
+            let video = options.encoding.video.get_or_insert_with(VideoSettings::default);
+            if let Some(codec) = self.video_codec {
+                video.codec = codec;
+            }
+            if let Some(crf) = self.crf {
+                video.quality = Quality::Crf(crf);
+            }
+            if let Some(kbps) = self.video_bitrate {
+                video.quality = Quality::Bitrate(kbps);
+            }
+            if self.encoder_preset.is_some() {
+                video.preset = self.encoder_preset;
+            }
+            if self.max_height.is_some() {
+                video.max_height = self.max_height;
+            }
+            if self.fps.is_some() {
+                video.fps = self.fps;
+            }
+        }
+
+        if self.audio_codec.is_some() || self.audio_bitrate.is_some() {
+            let audio = options.encoding.audio.get_or_insert_with(AudioSettings::default);
+            if let Some(codec) = self.audio_codec {
+                audio.codec = codec;
+            }
+            if self.audio_bitrate.is_some() {
+                audio.bitrate_kbps = self.audio_bitrate;
+            }
+        }
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Convert a single MTS file to MP4
+    /// Convert a single video file to MP4 or another --format
     File {
-        /// Input MTS file path
+        /// Input video file path
         #[arg(short, long)]
         input: PathBuf,
         
@@ -22,7 +219,7 @@ enum Commands {
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
-    /// Convert all MTS files in a directory to MP4
+    /// Convert all video files in a directory to MP4 or another --format
     Directory {
         /// Input directory path
         #[arg(short, long)]
@@ -31,6 +228,101 @@ enum Commands {
         /// Output directory path (optional)
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        /// Number of files to convert at the same time (defaults to the number of CPU cores)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Keep converting the remaining files when one fails
+        #[arg(long)]
+        continue_on_error: bool,
+
+        /// Also convert files in subdirectories, mirroring the folder structure in the output directory
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Only convert files whose path relative to the input matches this glob (repeatable)
+        #[arg(long, value_name = "GLOB", value_parser = Pattern::new)]
+        include: Vec<Pattern>,
+
+        /// Skip files whose path relative to the input matches this glob (repeatable)
+        #[arg(long, value_name = "GLOB", value_parser = Pattern::new)]
+        exclude: Vec<Pattern>,
+
+        /// Skip files an earlier, interrupted run already converted, retrying failed ones
+        #[arg(long)]
+        resume: bool,
+
+        /// File extensions to convert, comma separated (defaults to mts,m2ts,ts,mod,tod,avi,mov,mkv,vob,3gp,wmv)
+        #[arg(long = "ext", value_name = "EXT", value_delimiter = ',')]
+        extensions: Vec<String>,
+
+        /// Also convert files with other extensions that ffprobe recognises as video
+        #[arg(long)]
+        probe_inputs: bool,
+
+        /// Convert each file of a recording the camera split into several on its own instead of joining them
+        #[arg(long)]
+        keep_segments: bool,
+
+        /// Name outputs after when each clip was recorded, e.g. 2023-07-14_09-30-12.mp4; short for
+        /// --output-name "{date}_{time}"
+        #[arg(long)]
+        name_by_date: bool,
+    },
+    /// Save a poster frame, and optionally a contact sheet, for a video file or every video in a directory
+    Thumbnails {
+        /// Input video file or directory path
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output directory path (optional)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Also process videos in subdirectories, mirroring the folder structure in the output directory
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// Save a short looping GIF or WebP preview of a video file
+    Preview {
+        /// Input video file path
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Output directory path (optional)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Where the preview starts: a percentage like 25%, seconds, or mm:ss
+        #[arg(long, value_name = "TIME", default_value = "10%")]
+        from: ThumbnailTime,
+
+        /// Length of the preview in seconds
+        #[arg(long, value_name = "SECONDS", default_value_t = 3.0)]
+        length: f64,
+
+        /// Width of the preview in pixels
+        #[arg(long, value_name = "PIXELS", default_value_t = 480)]
+        width: u32,
+
+        /// Animation format: gif or webp
+        #[arg(long, value_name = "FORMAT", default_value_t = AnimationFormat::Gif)]
+        animation: AnimationFormat,
+
+        /// Frame rate of the preview
+        #[arg(long, value_name = "FPS", default_value_t = PreviewOptions::default().fps)]
+        preview_fps: f64,
+    },
+    /// Show the container and streams of a media file
+    Info {
+        /// Input media file path
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Print the information as JSON instead of a table
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -38,10 +330,44 @@ fn main() {
     let cli = Cli::parse();
     let service = ConverterService::new();
 
+    let mut options = ConversionOptions {
+        overwrite: cli.if_exists,
+        output_name: cli.output_name.clone().unwrap_or_default(),
+        format: cli.format,
+        normalize_loudness: cli.normalize_loudness,
+        metadata: MetadataTags { title: cli.title, comment: cli.comment, author: cli.author },
+        ..ConversionOptions::default()
+    };
+    options.trim = match (cli.start, cli.end, cli.duration) {
+        (None, None, None) => None,
+        (start, _, Some(duration)) => {
+            Some(TimeRange::with_duration(start, duration).unwrap_or_else(|e| exit_usage(&e)))
+        }
+        (start, end, None) => Some(TimeRange::new(start, end).unwrap_or_else(|e| exit_usage(&e))),
+    };
+    if matches!(cli.command, Commands::Preview { .. }) && cli.encoding.fps.is_some() {
+        exit_usage("--fps changes the frame rate of conversions; use --preview-fps for previews");
+    }
+    cli.encoding.apply(&mut options);
+    if cli.thumbnails.requested() {
+        options.thumbnails = Some(cli.thumbnails.options(cli.if_exists));
+    }
+    // Only conversions write the output format
+    if matches!(cli.command, Commands::File { .. } | Commands::Directory { .. }) {
+        if let Err(e) = options.format.check_encoding(&options.encoding) {
+            exit_with_error(&e);
+        }
+    }
+    if cli.verbose {
+        options.stderr_sink = Some(Arc::new(|line: &str| eprintln!("ffmpeg: {}", line)));
+    }
+    install_ctrlc_handler(options.cancel.clone());
+
     // Create a progress callback for CLI
-    let progress_callback = Arc::new(|progress: f32| {
-        print!("\rProgress: {}%", (progress * 100.0) as i32);
-        if progress >= 1.0 {
+    let progress_callback: ProgressEventCallback = Arc::new(|event: &ProgressEvent| {
+        print!("\r{:<100}", describe_progress(event));
+        let _ = io::stdout().flush();
+        if event.phase == ConversionPhase::Finished {
             println!();
         }
     });
@@ -49,17 +375,319 @@ fn main() {
     match cli.command {
         Commands::File { input, output } => {
             println!("Converting file: {}", input.display());
-            match service.convert_file(&input, output.as_deref(), Some(progress_callback)) {
-                Ok(_) => println!("Conversion completed successfully"),
-                Err(e) => eprintln!("Error: {}", e),
+            match service.convert_file(&input, output.as_deref(), &options, Some(progress_callback)) {
+                Ok(Conversion::Converted { output, plan }) => {
+                    println!("Conversion completed successfully: {} ({})", output.display(), plan)
+                }
+                Ok(Conversion::Skipped(output)) => println!("Skipped, output already exists: {}", output.display()),
+                Err(e) => exit_with_error(&e),
             }
         }
-        Commands::Directory { input, output } => {
+        Commands::Directory {
+            input,
+            output,
+            jobs,
+            continue_on_error,
+            recursive,
+            include,
+            exclude,
+            resume,
+            extensions,
+            probe_inputs,
+            keep_segments,
+            name_by_date,
+        } => {
+            if name_by_date {
+                if cli.output_name.is_some() {
+                    exit_usage("--name-by-date cannot be used with --output-name");
+                }
+                options.output_name = "{date}_{time}".parse().expect("the date template is valid");
+            }
             println!("Converting directory: {}", input.display());
-            match service.convert_directory(&input, output.as_deref(), Some(progress_callback)) {
-                Ok(_) => println!("Directory conversion completed successfully"),
-                Err(e) => eprintln!("Error: {}", e),
+            let mut batch_options = BatchOptions {
+                conversion: options,
+                continue_on_error,
+                recursive,
+                include,
+                exclude,
+                resume,
+                detect_by_content: probe_inputs,
+                join_segments: !keep_segments,
+                ..BatchOptions::default()
+            };
+            if !extensions.is_empty() {
+                batch_options.extensions =
+                    extensions.iter().map(|ext| ext.trim_start_matches('.').to_ascii_lowercase()).collect();
             }
+            if let Some(jobs) = jobs {
+                batch_options.workers = jobs;
+            }
+            match service.convert_directory(&input, output.as_deref(), &batch_options, Some(progress_callback)) {
+                Ok(report) => {
+                    print_report(&report);
+                    if report.cancelled() > 0 {
+                        eprintln!("Conversion cancelled");
+                        process::exit(130);
+                    }
+                    if report.has_failures() {
+                        process::exit(1);
+                    }
+                    println!("Directory conversion completed successfully");
+                }
+                Err(e) => exit_with_error(&e),
+            }
+        }
+        Commands::Thumbnails { input, output, recursive } => {
+            let thumbnail_options = cli.thumbnails.options(cli.if_exists);
+            if input.is_dir() {
+                let batch_options = BatchOptions { conversion: options, recursive, ..BatchOptions::default() };
+                let result =
+                    service.thumbnails_for_directory(&input, output.as_deref(), &batch_options, &thumbnail_options);
+                match result {
+                    Ok(entries) if entries.is_empty() => println!("No video files found"),
+                    Ok(entries) => {
+                        for entry in &entries {
+                            println!("{}", entry.input.display());
+                            match entry.result {
+                                Ok(ref thumbnails) => print_thumbnails(thumbnails),
+                                Err(ref e) => println!("  failed: {}", e),
+                            }
+                        }
+                        if entries.iter().any(|entry| entry.result.is_err()) {
+                            process::exit(1);
+                        }
+                    }
+                    Err(e) => exit_with_error(&e),
+                }
+            } else {
+                match service.thumbnails_for_file(&input, output.as_deref(), &thumbnail_options) {
+                    Ok(thumbnails) => print_thumbnails(&thumbnails),
+                    Err(e) => exit_with_error(&e),
+                }
+            }
+        }
+        Commands::Preview { input, output, from, length, width, animation, preview_fps } => {
+            if !(length.is_finite() && length > 0.0) {
+                exit_usage("the preview length must be a positive number of seconds");
+            }
+            if !(preview_fps.is_finite() && preview_fps > 0.0) {
+                exit_usage("the preview frame rate must be a positive number");
+            }
+            let preview = PreviewOptions {
+                format: animation,
+                start: from,
+                duration: Duration::from_secs_f64(length),
+                width,
+                fps: preview_fps,
+            };
+            match service.create_preview(&input, output.as_deref(), &preview, &options, Some(progress_callback)) {
+                Ok(Some(output)) => println!("Preview saved: {}", output.display()),
+                Ok(None) => println!("Skipped, preview already exists"),
+                Err(e) => exit_with_error(&e),
+            }
+        }
+        Commands::Info { input, json } => match probe(&input) {
+            Ok(info) if json => println!("{}", serde_json::to_string_pretty(&info).expect("media info serializes")),
+            Ok(info) => print_media_info(&info),
+            Err(e) => exit_with_error(&e),
+        },
+    }
+}
+
+fn print_thumbnails(thumbnails: &Thumbnails) {
+    let images = [("poster", &thumbnails.poster), ("contact sheet", &thumbnails.contact_sheet)];
+    for (name, path) in images {
+        if let Some(path) = path {
+            println!("  {}: {}", name, path.display());
+        }
+    }
+    if thumbnails.poster.is_none() {
+        println!("  skipped, poster already exists");
+    }
+}
+
+// The first Ctrl-C stops the running conversion cleanly, a second one exits immediately
+fn install_ctrlc_handler(cancel: CancellationToken) {
+    let result = ctrlc::set_handler(move || {
+        if cancel.is_cancelled() {
+            process::exit(130);
+        }
+        eprintln!("\nCancelling, press Ctrl-C again to exit immediately...");
+        cancel.cancel();
+    });
+    if let Err(e) = result {
+        eprintln!("Warning: could not install Ctrl-C handler: {}", e);
+    }
+}
+
+// Arguments clap accepted but that do not make sense together
+fn exit_usage(message: &str) -> ! {
+    eprintln!("Error: {}", message);
+    process::exit(2);
+}
+
+fn exit_with_error(error: &ConvertError) -> ! {
+    if let ConvertError::Cancelled = error {
+        eprintln!("Conversion cancelled");
+        process::exit(130);
+    }
+    eprintln!("Error: {}", error);
+    if let Some(stderr) = error.stderr_tail().filter(|tail| !tail.trim().is_empty()) {
+        eprintln!("ffmpeg output:");
+        for line in stderr.lines() {
+            eprintln!("  {}", line);
+        }
+    }
+    process::exit(1);
+}
+
+fn print_report(report: &BatchReport) {
+    if report.entries.is_empty() {
+        println!("No video files found");
+        return;
+    }
+
+    println!();
+    println!("{:<10} {:<40} {:>10} {:>10}  Details", "Status", "Input", "Time", "Size");
+    for entry in &report.entries {
+        let mut name = entry.input.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if !entry.joined.is_empty() {
+            name = format!("{} +{}", name, entry.joined.len());
         }
+        let (status, size, details) = match &entry.outcome {
+            FileOutcome::Converted { output, output_size, plan } => {
+                let details = if plan.is_remux() {
+                    output.display().to_string()
+                } else {
+                    format!("{} ({})", output.display(), plan)
+                };
+                ("converted", format_size(*output_size), details)
+            }
+            FileOutcome::Skipped { reason } => ("skipped", String::from("-"), reason.clone()),
+            FileOutcome::Failed { error } => ("FAILED", String::from("-"), error.to_string()),
+            FileOutcome::Cancelled => ("cancelled", String::from("-"), String::new()),
+        };
+        println!(
+            "{:<10} {:<40} {:>10} {:>10}  {}",
+            status,
+            name,
+            format_duration(entry.duration),
+            size,
+            details
+        );
+    }
+    println!(
+        "\n{} converted, {} skipped, {} failed, {} cancelled",
+        report.converted(),
+        report.skipped(),
+        report.failed(),
+        report.cancelled()
+    );
+}
+
+fn print_media_info(info: &MediaInfo) {
+    let unknown = || String::from("unknown");
+    println!("{:<12} {}", "File:", info.path.display());
+    let container = match info.container_long_name {
+        Some(ref long_name) => format!("{} ({})", info.container, long_name),
+        None => info.container.clone(),
+    };
+    println!("{:<12} {}", "Container:", container);
+    println!("{:<12} {}", "Duration:", info.duration.map(format_duration).unwrap_or_else(unknown));
+    println!("{:<12} {}", "Size:", info.size.map(format_size).unwrap_or_else(unknown));
+    println!("{:<12} {}", "Bitrate:", info.bit_rate.map(format_bitrate).unwrap_or_else(unknown));
+    println!("{:<12} {}", "Created:", info.creation_time.clone().unwrap_or_else(unknown));
+    // Only recordings on an AVCHD card have clip information next to them
+    if let Some(clip) = clip_info_for(&info.path) {
+        println!("{:<12} {}", "Recorded:", clip.recorded.map(|time| time.to_string()).unwrap_or_else(unknown));
+        println!("{:<12} {}", "Maker:", clip.camera.map(|camera| camera.maker_name()).unwrap_or_else(unknown));
+        println!("{:<12} {}", "Model code:", clip.camera.map(|camera| camera.model_code_hex()).unwrap_or_else(unknown));
+    }
+
+    println!();
+    println!("{:<4} {:<9} {:<12} Details", "#", "Type", "Codec");
+    for stream in &info.streams {
+        let kind = match stream.kind {
+            StreamKind::Video => "video",
+            StreamKind::Audio => "audio",
+            StreamKind::Subtitle => "subtitle",
+            StreamKind::Data => "data",
+            StreamKind::Other => "other",
+        };
+        let codec = stream.codec.as_deref().unwrap_or("unknown");
+        println!("{:<4} {:<9} {:<12} {}", stream.index, kind, codec, describe_stream(stream));
+    }
+}
+
+fn describe_stream(stream: &StreamInfo) -> String {
+    let mut details = Vec::new();
+    if let (Some(width), Some(height)) = (stream.width, stream.height) {
+        details.push(format!("{}x{}", width, height));
+    }
+    if let Some(rate) = stream.frame_rate {
+        details.push(format!("{:.2} fps", rate));
+    }
+    if let Some(ref pixel_format) = stream.pixel_format {
+        details.push(pixel_format.clone());
+    }
+    match (stream.channels, stream.channel_layout.as_ref()) {
+        (_, Some(layout)) => details.push(layout.clone()),
+        (Some(channels), None) => details.push(format!("{} ch", channels)),
+        (None, None) => {}
+    }
+    if let Some(rate) = stream.sample_rate {
+        details.push(format!("{} Hz", rate));
+    }
+    if let Some(rate) = stream.bit_rate {
+        details.push(format_bitrate(rate));
+    }
+    if let Some(ref language) = stream.language {
+        details.push(language.clone());
+    }
+    details.join(", ")
+}
+
+fn format_bitrate(bits_per_second: u64) -> String {
+    format!("{} kb/s", bits_per_second / 1000)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn describe_progress(event: &ProgressEvent) -> String {
+    let name = event.file.file_name().unwrap_or_default().to_string_lossy();
+    let mut line = format!(
+        "[{}/{}] {} {:>3}%",
+        event.file_index + 1,
+        event.file_count,
+        name,
+        (event.progress * 100.0) as i32
+    );
+    if event.file_count > 1 {
+        line.push_str(&format!(" (overall {}%)", (event.overall_progress * 100.0) as i32));
+    }
+    match event.phase {
+        ConversionPhase::Probing => line.push_str(" probing"),
+        ConversionPhase::Finalizing => line.push_str(" finalizing"),
+        ConversionPhase::Converting | ConversionPhase::Finished => {}
+    }
+    if let Some(speed) = event.speed {
+        line.push_str(&format!(" speed {:.1}x", speed));
+    }
+    if let Some(eta) = event.eta.filter(|_| event.phase == ConversionPhase::Converting) {
+        line.push_str(&format!(" ETA {}", format_duration(eta)));
     }
+    line
 }
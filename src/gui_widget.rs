@@ -1,32 +1,107 @@
-use iced::widget::{button, column, container, row, text, progress_bar};
-use iced::{Application, Command, Element, Length, Theme, Alignment};
+use iced::widget::{button, checkbox, column, container, pick_list, row, text, text_input, progress_bar};
+use glob::Pattern;
+use iced::{subscription, Application, Command, Element, Length, Subscription, Theme, Alignment};
 use std::path::PathBuf;
 use rfd::FileDialog;
-use std::sync::{mpsc, Arc};
+use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
 
+use crate::video::progress::format_duration;
+use crate::video::{
+    BatchOptions, BatchReport, CancellationToken, Conversion, ConversionOptions, ConversionPhase, ConvertError,
+    AnimationFormat, FileOutcome, MetadataTags, OutputFormat, OutputTemplate, OverwritePolicy, Preset, PreviewOptions,
+    ProgressEvent, ProgressEventCallback, StreamMode, ThumbnailOptions, ThumbnailTime, INPUT_EXTENSIONS,
+};
 use crate::ConverterService;
 
+// Frames in the contact sheet saved alongside each video when thumbnails are on
+const CONTACT_SHEET_FRAMES: u32 = 9;
+
 #[derive(Debug, Clone)]
 pub enum Message {
     SelectInputPath,
     SelectOutputPath,
     InputPathSelected(Option<PathBuf>),
     OutputPathSelected(Option<PathBuf>),
+    OutputNameChanged(String),
+    RecursiveToggled(bool),
+    ResumeToggled(bool),
+    DetectByContentToggled(bool),
+    JoinSegmentsToggled(bool),
+    IncludePatternsChanged(String),
+    ExcludePatternsChanged(String),
+    TitleChanged(String),
+    CommentChanged(String),
+    AuthorChanged(String),
+    OverwritePolicySelected(OverwritePolicy),
+    FormatSelected(OutputFormat),
+    NormalizeLoudnessToggled(bool),
+    ThumbnailsToggled(bool),
+    StreamModeSelected(StreamMode),
+    PresetSelected(Preset),
+    AnimationFormatSelected(AnimationFormat),
+    PreviewStartChanged(String),
+    PreviewLengthChanged(String),
+    PreviewWidthChanged(String),
+    PreviewFpsChanged(String),
     ConvertSingleFile,
     ConvertDirectory,
-    ConversionProgress(f32),
-    ConversionComplete(Result<(), String>),
+    CreatePreview,
+    CancelConversion,
+    ConversionProgress(ProgressEvent),
+    ConversionComplete(Result<String, String>),
+    ConversionCancelled,
+}
+
+// What the Convert and Preview buttons start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JobKind {
+    File,
+    Directory,
+    Preview,
+}
+
+// Messages from the worker thread of the running conversion, drained by `subscription`
+#[derive(Debug)]
+pub struct ConversionJob {
+    id: u64,
+    receiver: Arc<Mutex<mpsc::UnboundedReceiver<Message>>>,
+    cancel: CancellationToken,
 }
 
 #[derive(Debug)]
 pub struct ConverterGui {
     pub input_path: String,
     pub output_path: String,
+    pub output_name: String,
+    pub recursive: bool,
+    pub resume: bool,
+    pub detect_by_content: bool,
+    pub join_segments: bool,
+    pub include_patterns: String,
+    pub exclude_patterns: String,
+    pub title: String,
+    pub comment: String,
+    pub author: String,
+    pub overwrite: OverwritePolicy,
+    pub format: OutputFormat,
+    pub normalize_loudness: bool,
+    pub thumbnails: bool,
+    pub animation_format: AnimationFormat,
+    pub preview_start: String,
+    pub preview_length: String,
+    pub preview_width: String,
+    pub preview_fps: String,
+    pub stream_mode: StreamMode,
+    pub preset: Option<Preset>,
     pub status_message: String,
     pub conversion_progress: f32,
     pub is_converting: bool,
     pub service: ConverterService,
+    pub job: Option<ConversionJob>,
+    next_job_id: u64,
 }
 
 impl Application for ConverterGui {
@@ -40,10 +115,33 @@ impl Application for ConverterGui {
             Self {
                 input_path: String::new(),
                 output_path: String::new(),
+                output_name: String::new(),
+                recursive: false,
+                resume: false,
+                detect_by_content: false,
+                join_segments: true,
+                include_patterns: String::new(),
+                exclude_patterns: String::new(),
+                title: String::new(),
+                comment: String::new(),
+                author: String::new(),
+                overwrite: OverwritePolicy::default(),
+                format: OutputFormat::default(),
+                normalize_loudness: false,
+                thumbnails: false,
+                animation_format: AnimationFormat::default(),
+                preview_start: String::new(),
+                preview_length: String::new(),
+                preview_width: String::new(),
+                preview_fps: String::new(),
+                stream_mode: StreamMode::default(),
+                preset: None,
                 status_message: String::from("Select a file or directory to convert"),
                 conversion_progress: 0.0,
                 is_converting: false,
                 service: ConverterService::new(),
+                job: None,
+                next_job_id: 0,
             },
             Command::none(),
         )
@@ -57,7 +155,9 @@ impl Application for ConverterGui {
         match message {
             Message::SelectInputPath => {
                 if let Some(path) = FileDialog::new()
-                    .add_filter("MTS Video", &["MTS", "mts"])
+                    .add_filter("Video", &input_filter_extensions())
+                    .add_filter("AVCHD Video", &["MTS", "mts", "M2TS", "m2ts"])
+                    .add_filter("All files", &["*"])
                     .pick_file()
                 {
                     self.input_path = path.to_string_lossy().into_owned();
@@ -87,112 +187,123 @@ impl Application for ConverterGui {
                 Command::none()
             }
             Message::OutputPathSelected(None) => Command::none(),
+            Message::OutputNameChanged(name) => {
+                self.output_name = name;
+                Command::none()
+            }
+            Message::RecursiveToggled(recursive) => {
+                self.recursive = recursive;
+                Command::none()
+            }
+            Message::ResumeToggled(resume) => {
+                self.resume = resume;
+                Command::none()
+            }
+            Message::DetectByContentToggled(detect) => {
+                self.detect_by_content = detect;
+                Command::none()
+            }
+            Message::JoinSegmentsToggled(join) => {
+                self.join_segments = join;
+                Command::none()
+            }
+            Message::IncludePatternsChanged(patterns) => {
+                self.include_patterns = patterns;
+                Command::none()
+            }
+            Message::ExcludePatternsChanged(patterns) => {
+                self.exclude_patterns = patterns;
+                Command::none()
+            }
+            Message::TitleChanged(title) => {
+                self.title = title;
+                Command::none()
+            }
+            Message::CommentChanged(comment) => {
+                self.comment = comment;
+                Command::none()
+            }
+            Message::AuthorChanged(author) => {
+                self.author = author;
+                Command::none()
+            }
+            Message::OverwritePolicySelected(policy) => {
+                self.overwrite = policy;
+                Command::none()
+            }
+            Message::FormatSelected(format) => {
+                self.format = format;
+                Command::none()
+            }
+            Message::NormalizeLoudnessToggled(normalize) => {
+                self.normalize_loudness = normalize;
+                Command::none()
+            }
+            Message::ThumbnailsToggled(thumbnails) => {
+                self.thumbnails = thumbnails;
+                Command::none()
+            }
+            Message::StreamModeSelected(mode) => {
+                self.stream_mode = mode;
+                Command::none()
+            }
+            Message::PresetSelected(preset) => {
+                let mut options = ConversionOptions::default();
+                options.apply_preset(preset);
+                self.stream_mode = options.stream_mode;
+                self.preset = Some(preset);
+                Command::none()
+            }
+            Message::AnimationFormatSelected(format) => {
+                self.animation_format = format;
+                Command::none()
+            }
+            Message::PreviewStartChanged(start) => {
+                self.preview_start = start;
+                Command::none()
+            }
+            Message::PreviewLengthChanged(length) => {
+                self.preview_length = length;
+                Command::none()
+            }
+            Message::PreviewWidthChanged(width) => {
+                self.preview_width = width;
+                Command::none()
+            }
+            Message::PreviewFpsChanged(fps) => {
+                self.preview_fps = fps;
+                Command::none()
+            }
             Message::ConvertSingleFile => {
-                if !self.is_converting {
-                    self.is_converting = true;
-                    self.conversion_progress = 0.0;
-                    self.status_message = String::from("Converting...");
-                    
-                    let input = PathBuf::from(&self.input_path);
-                    let output = if self.output_path.is_empty() {
-                        None
-                    } else {
-                        Some(PathBuf::from(&self.output_path))
-                    };
-
-                    let service = self.service.clone();
-                    let input_path = input.clone();
-
-                    Command::perform(
-                        async move {
-                            let (tx, rx) = mpsc::channel();
-                            let tx_progress = tx.clone();
-                            let progress_callback = Arc::new(move |progress: f32| {
-                                let _ = tx_progress.send(Message::ConversionProgress(progress));
-                            });
-
-                            // Spawn conversion thread
-                            let tx_complete = tx.clone();
-                            thread::spawn(move || {
-                                let result = service.convert_file(&input_path, output.as_deref(), Some(progress_callback));
-                                let _ = tx_complete.send(Message::ConversionComplete(result.map_err(|e| e.to_string())));
-                            });
-
-                            // Handle messages
-                            let mut last_progress = Message::ConversionProgress(0.0);
-                            while let Ok(message) = rx.recv() {
-                                match message {
-                                    Message::ConversionComplete(_) => return message,
-                                    Message::ConversionProgress(_) => last_progress = message,
-                                    _ => continue,
-                                }
-                            }
-                            last_progress
-                        },
-                        std::convert::identity
-                    )
-                } else {
-                    Command::none()
-                }
+                self.start_conversion(JobKind::File);
+                Command::none()
             }
             Message::ConvertDirectory => {
-                if !self.is_converting {
-                    self.is_converting = true;
-                    self.conversion_progress = 0.0;
-                    self.status_message = String::from("Converting directory...");
-                    
-                    let input = PathBuf::from(&self.input_path);
-                    let output = if self.output_path.is_empty() {
-                        None
-                    } else {
-                        Some(PathBuf::from(&self.output_path))
-                    };
-
-                    let service = self.service.clone();
-                    let input_path = input.clone();
-
-                    Command::perform(
-                        async move {
-                            let (tx, rx) = mpsc::channel();
-                            let tx_progress = tx.clone();
-                            let progress_callback = Arc::new(move |progress: f32| {
-                                let _ = tx_progress.send(Message::ConversionProgress(progress));
-                            });
-
-                            // Spawn conversion thread
-                            let tx_complete = tx.clone();
-                            thread::spawn(move || {
-                                let result = service.convert_directory(&input_path, output.as_deref(), Some(progress_callback));
-                                let _ = tx_complete.send(Message::ConversionComplete(result.map_err(|e| e.to_string())));
-                            });
-
-                            // Handle messages
-                            let mut last_progress = Message::ConversionProgress(0.0);
-                            while let Ok(message) = rx.recv() {
-                                match message {
-                                    Message::ConversionComplete(_) => return message,
-                                    Message::ConversionProgress(_) => last_progress = message,
-                                    _ => continue,
-                                }
-                            }
-                            last_progress
-                        },
-                        std::convert::identity
-                    )
-                } else {
-                    Command::none()
+                self.start_conversion(JobKind::Directory);
+                Command::none()
+            }
+            Message::CreatePreview => {
+                self.start_conversion(JobKind::Preview);
+                Command::none()
+            }
+            Message::CancelConversion => {
+                if let Some(ref job) = self.job {
+                    job.cancel.cancel();
+                    self.status_message = String::from("Cancelling...");
                 }
+                Command::none()
             }
-            Message::ConversionProgress(progress) => {
-                self.conversion_progress = progress;
-                self.status_message = format!("Converting... {}%", (progress * 100.0) as i32);
+            Message::ConversionProgress(event) => {
+                self.conversion_progress = event.overall_progress;
+                self.status_message = describe_progress(&event);
                 Command::none()
             }
             Message::ConversionComplete(result) => {
                 self.is_converting = false;
+                self.job = None;
                 match result {
-                    Ok(_) => {
-                        self.status_message = String::from("Conversion completed successfully");
+                    Ok(summary) => {
+                        self.status_message = summary;
                         self.conversion_progress = 1.0;
                     }
                     Err(e) => {
@@ -202,10 +313,31 @@ impl Application for ConverterGui {
                 }
                 Command::none()
             }
+            Message::ConversionCancelled => {
+                self.is_converting = false;
+                self.job = None;
+                self.status_message = String::from("Conversion cancelled");
+                self.conversion_progress = 0.0;
+                Command::none()
+            }
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn subscription(&self) -> Subscription<Message> {
+        let Some(ref job) = self.job else {
+            return Subscription::none();
+        };
+        subscription::unfold(job.id, Arc::clone(&job.receiver), |receiver| async move {
+            let message = receiver.lock().await.recv().await;
+            match message {
+                Some(message) => (message, receiver),
+                // The worker is gone; wait until the job is dropped from the state
+                None => std::future::pending().await,
+            }
+        })
+    }
+
+    fn view(&self) -> Element<'_, Message> {
         let input_row = row![
             text("Input:").width(Length::Fixed(60.0)),
             text(&self.input_path).width(Length::Fill),
@@ -218,10 +350,20 @@ impl Application for ConverterGui {
             text("Output:").width(Length::Fixed(60.0)),
             text(&self.output_path).width(Length::Fill),
             button("Browse").on_press(Message::SelectOutputPath),
+            text_input("Names, e.g. {date:%Y}/{stem}", &self.output_name)
+                .on_input(Message::OutputNameChanged)
+                .width(Length::Fixed(240.0)),
         ]
         .spacing(10)
         .align_items(Alignment::Center);
 
+        let cancel_button = button("Cancel").width(Length::Fixed(80.0));
+        let cancel_button = if self.is_converting {
+            cancel_button.on_press(Message::CancelConversion)
+        } else {
+            cancel_button
+        };
+
         let button_row = row![
             button("Convert File")
                 .on_press(Message::ConvertSingleFile)
@@ -229,6 +371,56 @@ impl Application for ConverterGui {
             button("Convert Directory")
                 .on_press(Message::ConvertDirectory)
                 .width(Length::Fixed(120.0)),
+            button("Create Preview")
+                .on_press(Message::CreatePreview)
+                .width(Length::Fixed(110.0)),
+            pick_list(&AnimationFormat::ALL[..], Some(self.animation_format), Message::AnimationFormatSelected),
+            cancel_button,
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let preview_row = row![
+            text("Preview:"),
+            text_input("From (10%)", &self.preview_start).on_input(Message::PreviewStartChanged),
+            text_input("Seconds (3)", &self.preview_length).on_input(Message::PreviewLengthChanged),
+            text_input("Width (480)", &self.preview_width).on_input(Message::PreviewWidthChanged),
+            text_input("FPS (12)", &self.preview_fps).on_input(Message::PreviewFpsChanged),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let filter_row = row![
+            checkbox("Include subfolders", self.recursive, Message::RecursiveToggled),
+            checkbox("Resume previous run", self.resume, Message::ResumeToggled),
+            checkbox("Detect videos by content", self.detect_by_content, Message::DetectByContentToggled),
+            checkbox("Join split recordings", self.join_segments, Message::JoinSegmentsToggled),
+            text_input("Include patterns (e.g. **/*.MTS)", &self.include_patterns)
+                .on_input(Message::IncludePatternsChanged),
+            text_input("Exclude patterns", &self.exclude_patterns)
+                .on_input(Message::ExcludePatternsChanged),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let options_row = row![
+            text("If output exists:"),
+            pick_list(&OverwritePolicy::ALL[..], Some(self.overwrite), Message::OverwritePolicySelected),
+            text("Format:"),
+            pick_list(&OutputFormat::ALL[..], Some(self.format), Message::FormatSelected),
+            text("Streams:"),
+            pick_list(&StreamMode::ALL[..], Some(self.stream_mode), Message::StreamModeSelected),
+            pick_list(&Preset::ALL[..], self.preset, Message::PresetSelected).placeholder("Preset"),
+            checkbox("Normalize loudness", self.normalize_loudness, Message::NormalizeLoudnessToggled),
+            checkbox("Thumbnails", self.thumbnails, Message::ThumbnailsToggled),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let metadata_row = row![
+            text_input("Title", &self.title).on_input(Message::TitleChanged),
+            text_input("Comment", &self.comment).on_input(Message::CommentChanged),
+            text_input("Author", &self.author).on_input(Message::AuthorChanged),
         ]
         .spacing(10)
         .align_items(Alignment::Center);
@@ -239,6 +431,10 @@ impl Application for ConverterGui {
         let content = column![
             input_row,
             output_row,
+            filter_row,
+            options_row,
+            metadata_row,
+            preview_row,
             button_row,
             progress_bar,
             text(&self.status_message),
@@ -260,4 +456,232 @@ impl ConverterGui {
     pub fn run(settings: iced::Settings<()>) -> iced::Result {
         <Self as Application>::run(settings)
     }
+
+    fn start_conversion(&mut self, kind: JobKind) {
+        if self.is_converting {
+            return;
+        }
+        let patterns = parse_patterns(&self.include_patterns).and_then(|include| {
+            parse_patterns(&self.exclude_patterns).map(|exclude| (include, exclude))
+        });
+        let (include, exclude) = match patterns {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                self.status_message = format!("Invalid file pattern: {}", e);
+                return;
+            }
+        };
+        let output_name = match non_empty(&self.output_name).map(|name| name.parse::<OutputTemplate>()) {
+            None => OutputTemplate::default(),
+            Some(Ok(template)) => template,
+            Some(Err(e)) => {
+                self.status_message = format!("Invalid output name: {}", e);
+                return;
+            }
+        };
+        let preview = match kind {
+            JobKind::Preview => self.preview_options(),
+            JobKind::File | JobKind::Directory => Ok(PreviewOptions::default()),
+        };
+        let preview = match preview {
+            Ok(preview) => preview,
+            Err(e) => {
+                self.status_message = format!("Invalid preview settings: {}", e);
+                return;
+            }
+        };
+        let encoding = self.preset.map(Preset::encoding).unwrap_or_default();
+        // Previews are always GIF or WebP, whatever the format and preset say
+        let encoding_check = match kind {
+            JobKind::Preview => Ok(()),
+            JobKind::File | JobKind::Directory => self.format.check_encoding(&encoding),
+        };
+        if let Err(e) = encoding_check {
+            self.status_message = format!("The preset does not suit the format: {}", e);
+            return;
+        }
+        self.is_converting = true;
+        self.conversion_progress = 0.0;
+        self.status_message = match kind {
+            JobKind::File => String::from("Converting..."),
+            JobKind::Directory => String::from("Converting directory..."),
+            JobKind::Preview => String::from("Creating preview..."),
+        };
+
+        let input_path = PathBuf::from(&self.input_path);
+        let output = if self.output_path.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(&self.output_path))
+        };
+        let service = self.service.clone();
+        let recursive = self.recursive;
+        let resume = self.resume;
+        let detect_by_content = self.detect_by_content;
+        let join_segments = self.join_segments;
+        let options = ConversionOptions {
+            overwrite: self.overwrite,
+            output_name,
+            format: self.format,
+            stream_mode: self.stream_mode,
+            encoding,
+            normalize_loudness: self.normalize_loudness,
+            metadata: MetadataTags {
+                title: non_empty(&self.title),
+                comment: non_empty(&self.comment),
+                author: non_empty(&self.author),
+            },
+            thumbnails: self.thumbnails.then(|| ThumbnailOptions {
+                contact_sheet: Some(CONTACT_SHEET_FRAMES),
+                overwrite: self.overwrite,
+                ..ThumbnailOptions::default()
+            }),
+            ..ConversionOptions::default()
+        };
+        let cancel = options.cancel.clone();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let tx_progress = tx.clone();
+        let progress_callback: ProgressEventCallback = Arc::new(move |event: &ProgressEvent| {
+            let _ = tx_progress.send(Message::ConversionProgress(event.clone()));
+        });
+
+        // Spawn conversion thread
+        thread::spawn(move || {
+            let result = match kind {
+                JobKind::Directory => {
+                    let options = BatchOptions {
+                        conversion: options,
+                        continue_on_error: true,
+                        recursive,
+                        include,
+                        exclude,
+                        resume,
+                        detect_by_content,
+                        join_segments,
+                        ..BatchOptions::default()
+                    };
+                    service
+                        .convert_directory(&input_path, output.as_deref(), &options, Some(progress_callback))
+                        .map(|report| summarize_report(&report))
+                }
+                JobKind::File => service
+                    .convert_file(&input_path, output.as_deref(), &options, Some(progress_callback))
+                    .map(|conversion| match conversion {
+                        Conversion::Converted { plan, .. } => format!("Conversion completed successfully ({})", plan),
+                        Conversion::Skipped(output) => format!("Skipped, {} already exists", output.display()),
+                    }),
+                JobKind::Preview => service
+                    .create_preview(&input_path, output.as_deref(), &preview, &options, Some(progress_callback))
+                    .map(|created| match created {
+                        Some(output) => format!("Preview saved: {}", output.display()),
+                        None => String::from("Skipped, the preview already exists"),
+                    }),
+            };
+            let message = match result {
+                Err(ConvertError::Cancelled) => Message::ConversionCancelled,
+                result => Message::ConversionComplete(result.map_err(|e| e.to_string())),
+            };
+            let _ = tx.send(message);
+        });
+
+        self.next_job_id += 1;
+        self.job = Some(ConversionJob {
+            id: self.next_job_id,
+            receiver: Arc::new(Mutex::new(rx)),
+            cancel,
+        });
+    }
+
+    // Empty fields keep the defaults, and the rest are checked like the CLI's `preview` arguments
+    fn preview_options(&self) -> Result<PreviewOptions, String> {
+        let defaults = PreviewOptions::default();
+        let start = match non_empty(&self.preview_start) {
+            Some(start) => start.parse::<ThumbnailTime>()?,
+            None => defaults.start,
+        };
+        let length = match non_empty(&self.preview_length) {
+            Some(length) => length.parse::<f64>().map_err(|_| format!("invalid length '{}'", length))?,
+            None => defaults.duration.as_secs_f64(),
+        };
+        if !(length.is_finite() && length > 0.0) {
+            return Err(String::from("the preview length must be a positive number of seconds"));
+        }
+        let width = match non_empty(&self.preview_width) {
+            Some(width) => width.parse::<u32>().map_err(|_| format!("invalid width '{}'", width))?,
+            None => defaults.width,
+        };
+        let fps = match non_empty(&self.preview_fps) {
+            Some(fps) => fps.parse::<f64>().map_err(|_| format!("invalid frame rate '{}'", fps))?,
+            None => defaults.fps,
+        };
+        if !(fps.is_finite() && fps > 0.0) {
+            return Err(String::from("the preview frame rate must be a positive number"));
+        }
+        let duration = Duration::from_secs_f64(length);
+        Ok(PreviewOptions { format: self.animation_format, start, duration, width, fps })
+    }
+}
+
+// Patterns are entered comma separated, e.g. `**/STREAM/*.MTS, 2023-*/**`
+fn parse_patterns(patterns: &str) -> Result<Vec<Pattern>, glob::PatternError> {
+    patterns
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .map(Pattern::new)
+        .collect()
+}
+
+// An empty field means no tag, rather than an empty one
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.trim()).filter(|value| !value.is_empty()).map(String::from)
+}
+
+// Some platforms match dialog filters case-sensitively, and cameras write upper-case names
+fn input_filter_extensions() -> Vec<String> {
+    INPUT_EXTENSIONS
+        .iter()
+        .flat_map(|ext| [ext.to_string(), ext.to_ascii_uppercase()])
+        .collect()
+}
+
+fn summarize_report(report: &BatchReport) -> String {
+    if report.entries.is_empty() {
+        return String::from("No video files found in the directory");
+    }
+    let mut summary = format!(
+        "Converted {} of {} files ({} skipped, {} failed, {} cancelled)",
+        report.converted(),
+        report.entries.len(),
+        report.skipped(),
+        report.failed(),
+        report.cancelled()
+    );
+    for entry in &report.entries {
+        if let FileOutcome::Failed { error } = &entry.outcome {
+            let name = entry.input.file_name().unwrap_or_default().to_string_lossy();
+            summary.push_str(&format!("\n{}: {}", name, error));
+        }
+    }
+    summary
+}
+
+fn describe_progress(event: &ProgressEvent) -> String {
+    let name = event.file.file_name().unwrap_or_default().to_string_lossy();
+    let mut status = match event.phase {
+        ConversionPhase::Probing => format!("Analyzing {}...", name),
+        ConversionPhase::Finalizing | ConversionPhase::Finished => format!("Finishing {}...", name),
+        ConversionPhase::Converting => format!("Converting {}... {}%", name, (event.progress * 100.0) as i32),
+    };
+    if event.file_count > 1 {
+        status.push_str(&format!(" (file {} of {})", event.file_index + 1, event.file_count));
+    }
+    if let Some(speed) = event.speed {
+        status.push_str(&format!(" - {:.1}x", speed));
+    }
+    if let Some(eta) = event.eta.filter(|_| event.phase == ConversionPhase::Converting) {
+        status.push_str(&format!(", ETA {}", format_duration(eta)));
+    }
+    status
 }
@@ -1,7 +1,11 @@
-use std::path::Path;
-use crate::video::{convert_mts_to_mp4, convert_mts_files_in_directory, ProgressCallback};
+use std::path::{Path, PathBuf};
+use crate::video::{
+    convert_mts_files_in_directory_with_options, convert_mts_to_mp4_with_options, create_preview, create_thumbnails,
+    create_thumbnails_in_directory, BatchOptions, BatchReport, Conversion, ConversionOptions, ConvertError,
+    PreviewOptions, ProgressEventCallback, ThumbnailEntry, ThumbnailOptions, Thumbnails,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ConverterService;
 
 impl ConverterService {
@@ -13,10 +17,11 @@ impl ConverterService {
         &self,
         input: &Path,
         output: Option<&Path>,
-        progress_callback: Option<ProgressCallback>
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        options: &ConversionOptions,
+        progress_callback: Option<ProgressEventCallback>
+    ) -> Result<Conversion, ConvertError> {
         println!("Starting file conversion: {}", input.display());
-        let result = convert_mts_to_mp4(input, output, progress_callback);
+        let result = convert_mts_to_mp4_with_options(input, output, options, progress_callback);
         match &result {
             Ok(_) => println!("File conversion completed: {}", input.display()),
             Err(e) => println!("File conversion failed: {}", e),
@@ -28,14 +33,73 @@ impl ConverterService {
         &self,
         input: &Path,
         output: Option<&Path>,
-        progress_callback: Option<ProgressCallback>
-    ) -> Result<(), Box<dyn std::error::Error>> {
+        options: &BatchOptions,
+        progress_callback: Option<ProgressEventCallback>
+    ) -> Result<BatchReport, ConvertError> {
         println!("Starting directory conversion: {}", input.display());
-        let result = convert_mts_files_in_directory(input, output, progress_callback);
+        let result = convert_mts_files_in_directory_with_options(input, output, options, progress_callback);
         match &result {
-            Ok(_) => println!("Directory conversion completed: {}", input.display()),
+            Ok(report) => println!(
+                "Directory conversion completed: {} ({} converted, {} skipped, {} failed, {} cancelled)",
+                input.display(),
+                report.converted(),
+                report.skipped(),
+                report.failed(),
+                report.cancelled()
+            ),
             Err(e) => println!("Directory conversion failed: {}", e),
         }
         result
     }
-}
\ No newline at end of file
+
+    pub fn thumbnails_for_file(
+        &self,
+        input: &Path,
+        output: Option<&Path>,
+        options: &ThumbnailOptions,
+    ) -> Result<Thumbnails, ConvertError> {
+        println!("Creating thumbnails: {}", input.display());
+        let result = create_thumbnails(input, output, options);
+        if let Err(e) = &result {
+            println!("Thumbnail creation failed: {}", e);
+        }
+        result
+    }
+
+    pub fn thumbnails_for_directory(
+        &self,
+        input: &Path,
+        output: Option<&Path>,
+        batch: &BatchOptions,
+        options: &ThumbnailOptions,
+    ) -> Result<Vec<ThumbnailEntry>, ConvertError> {
+        println!("Creating thumbnails for directory: {}", input.display());
+        let result = create_thumbnails_in_directory(input, output, batch, options);
+        match &result {
+            Ok(entries) => println!(
+                "Thumbnail creation completed: {} ({} of {} files)",
+                input.display(),
+                entries.iter().filter(|entry| entry.result.is_ok()).count(),
+                entries.len()
+            ),
+            Err(e) => println!("Thumbnail creation failed: {}", e),
+        }
+        result
+    }
+
+    pub fn create_preview(
+        &self,
+        input: &Path,
+        output: Option<&Path>,
+        preview: &PreviewOptions,
+        options: &ConversionOptions,
+        progress_callback: Option<ProgressEventCallback>
+    ) -> Result<Option<PathBuf>, ConvertError> {
+        println!("Creating preview: {}", input.display());
+        let result = create_preview(input, output, preview, options, progress_callback);
+        if let Err(e) = &result {
+            println!("Preview creation failed: {}", e);
+        }
+        result
+    }
+}
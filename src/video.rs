@@ -1,64 +1,372 @@
 use std::process::{Command, Stdio};
-use std::path::Path;
-use std::fs;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
 use std::io::{BufRead, BufReader};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+pub mod avchd;
+pub mod batch;
+pub mod cancel;
+pub mod encoding;
+pub mod error;
+pub mod format;
+pub mod journal;
+pub mod metadata;
+pub mod naming;
+pub mod options;
+mod output;
+pub mod plan;
+pub mod preview;
+pub mod probe;
+pub mod progress;
+pub mod segments;
+mod stderr;
+pub mod thumbnail;
+pub mod trim;
+
+use encoding::LOUDNESS_FILTER;
+use output::PendingOutput;
+use progress::{ProgressReporter, ProgressTotals, ProgressTracker};
+use segments::{probe_joined, ConcatList};
+use stderr::StderrCollector;
+
+pub use avchd::{read_clip_info, read_playlist, Camera, ClipInfo, PlayItem, Playlist, RecordingTime};
+pub use batch::{
+    convert_mts_files_in_directory_with_options, BatchEntry, BatchOptions, BatchReport, FileOutcome, INPUT_EXTENSIONS,
+};
+pub use cancel::CancellationToken;
+pub use encoding::{AudioCodec, AudioSettings, EncodingSettings, Preset, Quality, VideoCodec, VideoSettings};
+pub use error::ConvertError;
+pub use format::OutputFormat;
+pub use journal::{JobState, Journal, JournalEntry, SegmentFingerprint, JOURNAL_FILE_NAME};
+pub use metadata::MetadataTags;
+pub use naming::OutputTemplate;
+pub use options::{ConversionOptions, OverwritePolicy};
+pub use plan::{ConversionPlan, StreamAction, StreamMode, StreamPlan};
+pub use preview::{create_preview, AnimationFormat, PreviewOptions};
+pub use probe::{probe, MediaInfo, StreamInfo, StreamKind};
+pub use progress::{ConversionPhase, ProgressEvent, ProgressEventCallback};
+pub use stderr::LogSink;
+pub use thumbnail::{
+    create_thumbnails, create_thumbnails_in_directory, ImageFormat, ThumbnailEntry, ThumbnailOptions, ThumbnailTime,
+    Thumbnails,
+};
+pub use trim::TimeRange;
+
+// How many trailing lines of ffmpeg's stderr are kept for error reports
+const STDERR_TAIL_LINES: usize = 20;
+
+// How often a running ffmpeg is checked for cancellation
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 pub type ProgressCallback = Arc<dyn Fn(f32) + Send + Sync + 'static>;
 
+/// Adapts a plain fraction callback to the event-based API by forwarding `overall_progress`.
+pub fn progress_adapter(callback: ProgressCallback) -> ProgressEventCallback {
+    Arc::new(move |event: &ProgressEvent| callback(event.overall_progress))
+}
+
 pub fn convert_mts_to_mp4(
     input_path: &Path,
     output_dir: Option<&Path>,
     progress_callback: Option<ProgressCallback>
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), ConvertError> {
+    convert_mts_to_mp4_with_options(
+        input_path,
+        output_dir,
+        &ConversionOptions::default(),
+        progress_callback.map(progress_adapter),
+    )
+    .map(|_| ())
+}
+
+/// What a single-file conversion did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conversion {
+    Converted { output: PathBuf, plan: ConversionPlan },
+    /// The output already existed and [`OverwritePolicy::Skip`] left it untouched.
+    Skipped(PathBuf),
+}
+
+impl Conversion {
+    pub fn output(&self) -> &Path {
+        match self {
+            Conversion::Converted { output, .. } | Conversion::Skipped(output) => output,
+        }
+    }
+}
+
+pub fn convert_mts_to_mp4_with_options(
+    input_path: &Path,
+    output_dir: Option<&Path>,
+    options: &ConversionOptions,
+    event_callback: Option<ProgressEventCallback>
+) -> Result<Conversion, ConvertError> {
+    convert_segments_with_options(&[input_path.to_path_buf()], output_dir, options, event_callback)
+}
+
+/// Converts consecutive segments of one recording, as AVCHD cameras split long recordings into,
+/// to a single output named after the first segment by the options' output template.
+pub fn convert_segments_with_options(
+    segments: &[PathBuf],
+    output_dir: Option<&Path>,
+    options: &ConversionOptions,
+    event_callback: Option<ProgressEventCallback>
+) -> Result<Conversion, ConvertError> {
+    let Some(input_path) = segments.first() else {
+        return Err(ConvertError::InputNotFound(PathBuf::new()));
+    };
     let output_dir = output_dir.unwrap_or_else(|| input_path.parent().unwrap());
-    let output_filename = input_path.file_stem().unwrap_or_default().to_str().unwrap_or("converted");
-    let output_path = output_dir.join(format!("{}.mp4", output_filename));
-
-    let mut cmd = Command::new("ffmpeg")
-        .arg("-i")
-        .arg(input_path)
-        .arg("-c")
-        .arg("copy")
-        .arg("-bsf:v")
-        .arg("h264_mp4toannexb")
+    let output_path = output_dir.join(options.output_name.render(input_path, 1, options.format.extension()));
+    convert_segments_to(segments, &output_path, options, event_callback)
+}
+
+// Converts to the given output path, before the overwrite policy is applied to it
+pub(crate) fn convert_segments_to(
+    segments: &[PathBuf],
+    output_path: &Path,
+    options: &ConversionOptions,
+    event_callback: Option<ProgressEventCallback>
+) -> Result<Conversion, ConvertError> {
+    let Some(input_path) = segments.first() else {
+        return Err(ConvertError::InputNotFound(PathBuf::new()));
+    };
+    if let Some(missing) = segments.iter().find(|segment| !segment.is_file()) {
+        return Err(ConvertError::InputNotFound(missing.clone()));
+    }
+    let output_path = output_path.to_path_buf();
+    // ffmpeg cannot read and write the same file, whatever the overwrite policy says
+    if segments.contains(&output_path) {
+        return Err(ConvertError::OutputExists(output_path));
+    }
+
+    options.format.check_encoding(&options.encoding)?;
+
+    let reporter = ProgressReporter::new(input_path.to_path_buf(), event_callback);
+    let Some(output_path) = options.overwrite.resolve(&output_path)? else {
+        let mut tracker = ProgressTracker::new(ProgressTotals::default());
+        tracker.finish();
+        reporter.report(ConversionPhase::Finished, &tracker);
+        println!("Skipped {}: {} already exists", input_path.display(), output_path.display());
+        return Ok(Conversion::Skipped(output_path));
+    };
+    reporter.report(ConversionPhase::Probing, &ProgressTracker::new(ProgressTotals::default()));
+    let info = if segments.len() == 1 { probe(input_path).ok() } else { probe_joined(segments) };
+    if let (Some(range), Some(duration)) = (options.trim, info.as_ref().and_then(|info| info.duration)) {
+        if range.start >= duration {
+            return Err(ConvertError::TrimOutOfRange { path: input_path.to_path_buf(), start: range.start, duration });
+        }
+    }
+    let tracker = ProgressTracker::new(progress_totals(segments, info.as_ref(), options.trim));
+    // A trimmed clip was recorded as much later as its start
+    let recorded = metadata::recording_time(input_path, info.as_ref())
+        .map(|time| time + options.trim.map(|range| range.start).unwrap_or_default());
+    let plan = info.map(|info| plan_conversion(&info, options)).unwrap_or_default();
+    if !plan.streams.is_empty() && plan.kept().next().is_none() {
+        return Err(ConvertError::NoUsableStreams { path: input_path.to_path_buf(), format: options.format });
+    }
+    if options.cancel.is_cancelled() {
+        return Err(ConvertError::Cancelled);
+    }
+    println!("Stream plan for {}: {}", input_path.display(), plan);
+    reporter.report(ConversionPhase::Converting, &tracker);
+
+    // Output templates may name subdirectories
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| ConvertError::io(parent, e))?;
+    }
+    let pending = PendingOutput::new(&output_path);
+    let mut command = ffmpeg_command();
+    // The overwrite policy has already been applied; `-y` only replaces a stale temporary file.
+    // The muxer is named because the temporary file's extension does not identify it.
+    command.arg("-y");
+    // Seeking before `-i` is fast. Decoded video is cut at the exact frame, but copied video can only
+    // start at a keyframe, so it starts at the one before `start` and its timestamps are shifted to zero.
+    let copies_video = if plan.streams.is_empty() {
+        options.encoding.video.is_none() && !options.format.is_audio_only()
+    } else {
+        plan.copies_video()
+    };
+    if let Some(range) = options.trim {
+        command.arg("-ss").arg(format!("{:.3}", range.start.as_secs_f64()));
+        println!("Trimming {} from {}{}", input_path.display(), range, if copies_video { " at keyframes" } else { "" });
+    }
+    // Segments are read through a list file, which is removed once the conversion is over
+    let concat_list = if segments.len() > 1 { Some(ConcatList::write(&output_path, segments)?) } else { None };
+    match concat_list {
+        Some(ref list) => command.args(["-f", "concat", "-safe", "0", "-i"]).arg(list.path()),
+        None => command.arg("-i").arg(input_path),
+    };
+    if let Some(length) = options.trim.and_then(|range| range.length(None)) {
+        command.arg("-t").arg(format!("{:.3}", length.as_secs_f64()));
+    }
+    add_stream_args(&mut command, &plan, options);
+    if options.trim.is_some() && copies_video {
+        command.args(["-avoid_negative_ts", "make_zero"]);
+    }
+    metadata::add_metadata_args(&mut command, &options.metadata, recorded);
+    command
         .arg("-progress")
         .arg("-")
-        .arg(output_path.to_str().unwrap())
+        .arg("-nostats")
+        .args(options.format.muxer_args())
+        .arg(pending.temp_path());
+    let mut tracker = run_ffmpeg(command, input_path, &output_path, options, tracker, &reporter)?;
+
+    reporter.report(ConversionPhase::Finalizing, &tracker);
+    let expected: Vec<StreamKind> = plan.kept().map(|stream| stream.kind).collect();
+    let output_path = pending.commit_media(&expected)?;
+    if let Some(recorded) = recorded {
+        if let Err(e) = metadata::set_modified(&output_path, recorded) {
+            println!("Warning: could not set the modification time of {}: {}", output_path.display(), e);
+        }
+    }
+    if let Some(ref thumbnails) = options.thumbnails {
+        add_thumbnails(&output_path, thumbnails, options.format);
+    }
+    tracker.finish();
+    reporter.report(ConversionPhase::Finished, &tracker);
+    println!("Successfully converted {} to {}", input_path.display(), options.format.description());
+    Ok(Conversion::Converted { output: output_path, plan })
+}
+
+// Thumbnails are a by-product, so failing to make them does not fail the conversion
+fn add_thumbnails(output_path: &Path, options: &ThumbnailOptions, format: OutputFormat) {
+    if format.is_audio_only() {
+        return;
+    }
+    if let Err(e) = create_thumbnails(output_path, output_path.parent(), options) {
+        println!("Warning: could not create thumbnails for {}: {}", output_path.display(), e);
+    }
+}
+
+fn plan_conversion(info: &MediaInfo, options: &ConversionOptions) -> ConversionPlan {
+    let plan = ConversionPlan::for_format(info, options.format, options.stream_mode);
+    let plan = if options.normalize_loudness { plan.with_audio_reencoded(options.format) } else { plan };
+    plan.with_encoding(&options.encoding)
+}
+
+// Maps the planned streams explicitly; without a plan ffmpeg picks its default streams
+fn add_stream_args(command: &mut Command, plan: &ConversionPlan, options: &ConversionOptions) {
+    let (encoding, format) = (&options.encoding, options.format);
+    if plan.streams.is_empty() {
+        // The more specific `-c:v` and `-c:a` override the blanket copy
+        command.args(["-c", "copy"]);
+        if format.is_audio_only() {
+            command.args(["-vn", "-sn", "-dn"]);
+        } else if let Some(ref video) = encoding.video {
+            command.args(video.args("v", format));
+        }
+        // The input's audio codec is unknown, so audio is re-encoded whenever it has to fit the format
+        if let Some(ref audio) = encoding.audio {
+            command.args(audio.args("a"));
+        } else if format.is_audio_only() || options.normalize_loudness {
+            command.arg("-c:a").args(format.default_encoder(StreamKind::Audio));
+        }
+        if options.normalize_loudness {
+            command.args(["-filter:a", LOUDNESS_FILTER]);
+        }
+        return;
+    }
+    for (output_index, stream) in plan.kept().enumerate() {
+        command.arg("-map").arg(format!("0:{}", stream.index));
+        let spec = output_index.to_string();
+        let settings = match stream.kind {
+            StreamKind::Video => encoding.video.as_ref().map(|video| video.args(&spec, format)),
+            StreamKind::Audio => encoding.audio.as_ref().map(|audio| audio.args(&spec)),
+            _ => None,
+        };
+        match (&stream.action, settings) {
+            (StreamAction::Transcode { .. }, Some(args)) => {
+                command.args(args);
+            }
+            (StreamAction::Transcode { encoder }, None) => {
+                command.arg(format!("-c:{}", spec)).arg(encoder);
+            }
+            _ => {
+                command.arg(format!("-c:{}", spec)).arg("copy");
+            }
+        }
+        if let Some(filter) = stream.bitstream_filter() {
+            command.arg(format!("-bsf:{}", spec)).arg(filter);
+        }
+        if stream.kind == StreamKind::Audio && options.normalize_loudness {
+            command.arg(format!("-filter:{}", spec)).arg(LOUDNESS_FILTER);
+        }
+    }
+}
+
+// Runs a prepared ffmpeg command to completion, reporting progress from its `-progress` output.
+// If the conversion is cancelled ffmpeg is killed; the caller's `PendingOutput` removes what it wrote.
+fn run_ffmpeg(
+    mut command: Command,
+    input_path: &Path,
+    output_path: &Path,
+    options: &ConversionOptions,
+    mut tracker: ProgressTracker,
+    reporter: &ProgressReporter,
+) -> Result<ProgressTracker, ConvertError> {
+    let mut child = command
+        .stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
-        .spawn()?;
-
-    if let Some(callback) = progress_callback {
-        if let Some(stdout) = cmd.stdout.take() {
-            let reader = BufReader::new(stdout);
-            let mut total_frames = 1000;
-
-            for line in reader.lines() {
-                if let Ok(line) = line {
-                    if line.starts_with("frame=") {
-                        if let Ok(current_frame) = line[6..].trim().parse::<i32>() {
-                            let progress = (current_frame as f32) / (total_frames as f32);
-                            callback(progress.min(1.0));
-                        }
-                    } else if line.starts_with("total_frames=") {
-                        if let Ok(frames) = line[13..].trim().parse::<i32>() {
-                            total_frames = frames;
-                        }
-                    }
-                }
+        .spawn()
+        .map_err(|e| ConvertError::spawn(input_path, e))?;
+
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| StderrCollector::spawn(pipe, STDERR_TAIL_LINES, options.stderr_sink.clone()));
+
+    // Always drain stdout, even without a callback, so ffmpeg never blocks on a full pipe.
+    // ffmpeg ends every block of statistics with a `progress=` line, so report once per block.
+    let stdout = child.stdout.take().expect("ffmpeg stdout is piped");
+    let stdout_reporter = reporter.clone();
+    let stdout_reader = thread::spawn(move || {
+        let reader = BufReader::new(stdout);
+        for line in reader.lines().map_while(Result::ok) {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            tracker.update(key, value);
+            if key == "progress" {
+                stdout_reporter.report(ConversionPhase::Converting, &tracker);
             }
-            callback(1.0);
         }
-    }
+        tracker
+    });
+
+    let status = loop {
+        if options.cancel.is_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => thread::sleep(CANCEL_POLL_INTERVAL),
+            Err(e) => {
+                let _ = child.kill();
+                return Err(ConvertError::io(input_path, e));
+            }
+        }
+    };
 
-    let status = cmd.wait()?;
+    let tracker = stdout_reader.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+    let stderr_tail = stderr.map(StderrCollector::finish).unwrap_or_default();
+
+    // A Ctrl-C in the terminal also reaches ffmpeg, which may exit on its own first
+    let status = match status {
+        Some(status) if !options.cancel.is_cancelled() => status,
+        _ => return Err(ConvertError::Cancelled),
+    };
 
     if status.success() {
-        println!("Successfully converted {} to MP4", input_path.display());
-        Ok(())
+        Ok(tracker)
     } else {
-        Err("Video conversion failed".into())
+        Err(ConvertError::from_ffmpeg_exit(input_path, output_path, status, stderr_tail))
     }
 }
 
@@ -66,47 +374,41 @@ pub fn convert_mts_files_in_directory(
     input_dir: &Path,
     output_dir: Option<&Path>,
     progress_callback: Option<ProgressCallback>
-) -> Result<(), Box<dyn std::error::Error>> {
-    let total_files = fs::read_dir(input_dir)?
-        .filter(|entry| {
-            entry.as_ref()
-                .map(|e| e.path().extension().map_or(false, |ext| ext.eq_ignore_ascii_case("mts")))
-                .unwrap_or(false)
-        })
-        .count();
-
-    if total_files == 0 {
-        return Ok(());
-    }
-
-    let mut completed_files = 0;
-
-    for entry in fs::read_dir(input_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("mts")) {
-            if let Some(ref callback) = progress_callback {
-                let callback = Arc::clone(callback);
-                let completed = completed_files;
-                let total = total_files;
-                
-                // Create a new thread-safe callback for this file
-                let file_callback: ProgressCallback = {
-                    let callback = Arc::clone(&callback);
-                    Arc::new(move |file_progress| {
-                        let overall_progress = (completed as f32 + file_progress) / (total as f32);
-                        callback(overall_progress);
-                    })
-                };
-                
-                convert_mts_to_mp4(&path, output_dir, Some(file_callback))?;
-            } else {
-                convert_mts_to_mp4(&path, output_dir, None)?;
+) -> Result<(), ConvertError> {
+    convert_mts_files_in_directory_with_options(
+        input_dir,
+        output_dir,
+        &BatchOptions::default(),
+        progress_callback.map(progress_adapter),
+    )
+    .map(|_| ())
+}
+
+// The executables can be overridden for installs outside PATH (and for tests)
+fn ffmpeg_command() -> Command {
+    Command::new(env::var_os("MAGIC_CONVERTER_FFMPEG").unwrap_or_else(|| "ffmpeg".into()))
+}
+
+fn ffprobe_command() -> Command {
+    Command::new(env::var_os("MAGIC_CONVERTER_FFPROBE").unwrap_or_else(|| "ffprobe".into()))
+}
+
+// Uses the probed duration and frame count so progress reflects the real clip length.
+// Falls back to the input size alone when ffprobe is unavailable or reports nothing useful.
+fn progress_totals(segments: &[PathBuf], info: Option<&MediaInfo>, trim: Option<TimeRange>) -> ProgressTotals {
+    let mut totals = info.map(MediaInfo::progress_totals).unwrap_or_default();
+    let sizes: Option<u64> = segments.iter().map(|segment| fs::metadata(segment).ok().map(|meta| meta.len())).sum();
+    totals.bytes = sizes.filter(|len| *len > 0);
+    if let Some(range) = trim {
+        // A trimmed clip has only its share of the frames
+        let clip = totals.duration;
+        totals.duration = range.length(clip);
+        totals.frames = match (totals.frames, clip, totals.duration) {
+            (Some(frames), Some(clip), Some(length)) if !clip.is_zero() => {
+                Some((frames as f64 * length.as_secs_f64() / clip.as_secs_f64()).round() as u64)
             }
-            completed_files += 1;
-        }
+            _ => None,
+        };
     }
-    
-    Ok(())
-}
\ No newline at end of file
+    totals
+}
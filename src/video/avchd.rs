@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::error::ConvertError;
+
+// Play item timestamps count ticks of the 45 kHz clock
+const TICKS_PER_SECOND: u64 = 45_000;
+// Connection conditions of a play item that carries on the previous one without a gap
+const SEAMLESS_CONNECTIONS: [u8; 2] = [5, 6];
+
+/// The local time a camera started recording, as it was set on the camera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RecordingTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl RecordingTime {
+    fn from_bcd(bytes: &[u8]) -> Option<Self> {
+        let digits = |byte: u8| {
+            let (high, low) = (byte >> 4, byte & 0x0f);
+            (high < 10 && low < 10).then_some(high * 10 + low)
+        };
+        let time = RecordingTime {
+            year: digits(bytes[0])? as u16 * 100 + digits(bytes[1])? as u16,
+            month: digits(bytes[2])?,
+            day: digits(bytes[3])?,
+            hour: digits(bytes[4])?,
+            minute: digits(bytes[5])?,
+            second: digits(bytes[6])?,
+        };
+        // Unset clocks are written as all zeroes or all ones
+        let valid = (1..=12).contains(&time.month)
+            && (1..=31).contains(&time.day)
+            && time.hour < 24
+            && time.minute < 60
+            && time.second < 60;
+        valid.then_some(time)
+    }
+}
+
+impl fmt::Display for RecordingTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// The camera that recorded a clip, as the maker and model codes AVCHD stores. Model codes are
+/// the maker's own numbering and are not mapped to model names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Camera {
+    pub maker_id: u16,
+    pub model_code: u16,
+}
+
+impl Camera {
+    /// The maker's name, for the makers whose code is known.
+    pub fn maker(&self) -> Option<&'static str> {
+        match self.maker_id {
+            0x0103 => Some("Panasonic"),
+            0x0108 => Some("Sony"),
+            0x1011 => Some("Canon"),
+            0x1104 => Some("JVC"),
+            _ => None,
+        }
+    }
+
+    /// The maker's name, or its code in hex for unknown makers.
+    pub fn maker_name(&self) -> String {
+        self.maker().map(String::from).unwrap_or_else(|| format!("{:04X}", self.maker_id))
+    }
+
+    /// The model code in hex, e.g. `0415`.
+    pub fn model_code_hex(&self) -> String {
+        format!("{:04X}", self.model_code)
+    }
+}
+
+/// Shows the maker and the model code, e.g. `Sony, model code 0415`.
+impl fmt::Display for Camera {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, model code {}", self.maker_name(), self.model_code_hex())
+    }
+}
+
+/// What a `.CPI` file says about the clip with the same number.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClipInfo {
+    pub recorded: Option<RecordingTime>,
+    pub camera: Option<Camera>,
+}
+
+/// One clip of a playlist, played from `in_time` to `out_time`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayItem {
+    /// The clip's number as it appears in file names, e.g. `00001`.
+    pub clip: String,
+    pub in_time: Duration,
+    pub out_time: Duration,
+    /// Whether the clip carries on the previous one without a gap, as when a camera splits a
+    /// long recording into several files.
+    pub seamless: bool,
+}
+
+/// The contents of a `.MPL` file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Playlist {
+    pub items: Vec<PlayItem>,
+    pub recorded: Option<RecordingTime>,
+    pub camera: Option<Camera>,
+}
+
+impl Playlist {
+    /// The clip numbers of each recording, joining seamlessly connected clips.
+    pub fn recordings(&self) -> Vec<Vec<&str>> {
+        let mut recordings: Vec<Vec<&str>> = Vec::new();
+        for item in &self.items {
+            match recordings.last_mut() {
+                Some(recording) if item.seamless => recording.push(&item.clip),
+                _ => recordings.push(vec![&item.clip]),
+            }
+        }
+        recordings
+    }
+}
+
+/// Parses a `.MPL` playlist from an AVCHD card's `BDMV/PLAYLIST` directory.
+pub fn read_playlist(path: &Path) -> Result<Playlist, ConvertError> {
+    let data = fs::read(path).map_err(|e| ConvertError::io(path, e))?;
+    parse_playlist(&data).ok_or_else(|| invalid(path, "not an AVCHD playlist"))
+}
+
+/// Parses a `.CPI` clip information file from an AVCHD card's `BDMV/CLIPINF` directory.
+pub fn read_clip_info(path: &Path) -> Result<ClipInfo, ConvertError> {
+    let data = fs::read(path).map_err(|e| ConvertError::io(path, e))?;
+    parse_clip_info(&data).ok_or_else(|| invalid(path, "not an AVCHD clip information file"))
+}
+
+/// The clip information of a recording in an AVCHD `STREAM` directory, if its `.CPI` file can be read.
+pub fn clip_info_for(stream_file: &Path) -> Option<ClipInfo> {
+    let bdmv = bdmv_dir(stream_file)?;
+    let stem = stream_file.file_stem()?.to_str()?;
+    let clipinf = find_entry(&bdmv, "CLIPINF")?;
+    // Blu-ray discs name the same file `.clpi`
+    let cpi = find_entry(&clipinf, &format!("{}.CPI", stem))
+        .or_else(|| find_entry(&clipinf, &format!("{}.clpi", stem)))?;
+    read_clip_info(&cpi).ok()
+}
+
+/// Which recordings of an AVCHD `STREAM` directory its playlists say continue one another.
+#[derive(Debug, Default)]
+pub(crate) struct SegmentLinks {
+    // Read once per `STREAM` directory; `None` where the card has no readable playlists
+    cards: HashMap<PathBuf, Option<CardLinks>>,
+}
+
+#[derive(Debug, Default)]
+struct CardLinks {
+    clips: HashSet<String>,
+    // (earlier, next) clip numbers of seamlessly connected play items
+    seamless: HashSet<(String, String)>,
+}
+
+impl SegmentLinks {
+    /// Whether `next` continues `earlier`, or `None` when no playlist lists both.
+    pub(crate) fn continues(&mut self, earlier: &Path, next: &Path) -> Option<bool> {
+        let stream_dir = earlier.parent()?;
+        let card = self.cards.entry(stream_dir.to_path_buf()).or_insert_with(|| card_links(earlier)).as_ref()?;
+        let (earlier, next) = (earlier.file_stem()?.to_str()?, next.file_stem()?.to_str()?);
+        if !card.clips.contains(earlier) || !card.clips.contains(next) {
+            return None;
+        }
+        Some(card.seamless.contains(&(earlier.to_string(), next.to_string())))
+    }
+}
+
+fn card_links(stream_file: &Path) -> Option<CardLinks> {
+    let playlist_dir = find_entry(&bdmv_dir(stream_file)?, "PLAYLIST")?;
+    let mut card = CardLinks::default();
+    for entry in fs::read_dir(&playlist_dir).ok()?.flatten() {
+        let path = entry.path();
+        if !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("mpl")) {
+            continue;
+        }
+        let Ok(playlist) = read_playlist(&path) else {
+            continue;
+        };
+        for recording in playlist.recordings() {
+            card.clips.extend(recording.iter().map(|clip| clip.to_string()));
+            card.seamless.extend(recording.windows(2).map(|pair| (pair[0].to_string(), pair[1].to_string())));
+        }
+    }
+    (!card.clips.is_empty()).then_some(card)
+}
+
+// `.../BDMV/STREAM/00001.MTS` -> `.../BDMV`
+fn bdmv_dir(stream_file: &Path) -> Option<PathBuf> {
+    let stream_dir = stream_file.parent()?;
+    if !stream_dir.file_name()?.to_str()?.eq_ignore_ascii_case("STREAM") {
+        return None;
+    }
+    stream_dir.parent().map(Path::to_path_buf)
+}
+
+// Cards written on one system may be read on another, so names are matched regardless of case
+fn find_entry(dir: &Path, name: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .find(|entry| entry.file_name().to_str().is_some_and(|entry_name| entry_name.eq_ignore_ascii_case(name)))
+        .map(|entry| entry.path())
+}
+
+fn invalid(path: &Path, message: &str) -> ConvertError {
+    ConvertError::io(path, io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn parse_playlist(data: &[u8]) -> Option<Playlist> {
+    if data.get(..4)? != b"MPLS" {
+        return None;
+    }
+    let list = read_u32(data, 8)? as usize;
+    let item_count = read_u16(data, list + 6)?;
+    let mut items = Vec::with_capacity(item_count as usize);
+    let mut pos = list + 10;
+    for _ in 0..item_count {
+        let length = read_u16(data, pos)? as usize;
+        let clip = std::str::from_utf8(data.get(pos + 2..pos + 7)?).ok()?.to_string();
+        let connection = data.get(pos + 12)? & 0x0f;
+        let ticks = |offset| read_u32(data, pos + offset).map(|ticks| ticks_to_duration(ticks as u64));
+        items.push(PlayItem {
+            clip,
+            in_time: ticks(14)?,
+            out_time: ticks(18)?,
+            seamless: !items.is_empty() && SEAMLESS_CONNECTIONS.contains(&connection),
+        });
+        pos += 2 + length;
+    }
+    let (recorded, camera) = extension_metadata(data, read_u32(data, 16)? as usize, b"PLEX");
+    Some(Playlist { items, recorded, camera })
+}
+
+fn parse_clip_info(data: &[u8]) -> Option<ClipInfo> {
+    if data.get(..4)? != b"HDMV" {
+        return None;
+    }
+    let (recorded, camera) = extension_metadata(data, read_u32(data, 24)? as usize, b"CLEX");
+    Some(ClipInfo { recorded, camera })
+}
+
+// The recording time and camera are in the AVCHD extension block, tagged `PLEX` in playlists and
+// `CLEX` in clip information. After the tag and 4 reserved bytes comes the offset of its metadata,
+// which holds the maker and model codes at bytes 4 and 6 and the local recording time as 7 BCD
+// bytes (`YYYY MM DD hh mm ss`) at byte 41.
+fn extension_metadata(data: &[u8], start: usize, tag: &[u8; 4]) -> (Option<RecordingTime>, Option<Camera>) {
+    let Some(meta) = extension_block(data, start, tag).and_then(|block| Some(block + read_u32(data, block + 8)? as usize))
+    else {
+        return (None, None);
+    };
+    let camera = read_u16(data, meta + 4)
+        .zip(read_u16(data, meta + 6))
+        .map(|(maker_id, model_code)| Camera { maker_id, model_code })
+        .filter(|camera| camera.maker_id != 0 && camera.maker_id != 0xffff);
+    let recorded = data.get(meta + 41..meta + 48).and_then(RecordingTime::from_bcd);
+    (recorded, camera)
+}
+
+// Blu-ray extension data: a length, an entry count at byte 11, then 12-byte entries that hold
+// their block's offset from `start` at byte 4
+fn extension_block(data: &[u8], start: usize, tag: &[u8; 4]) -> Option<usize> {
+    if start == 0 || read_u32(data, start)? == 0 {
+        return None;
+    }
+    let entry_count = *data.get(start + 11)? as usize;
+    (0..entry_count).find_map(|entry| {
+        let block = start + read_u32(data, start + 12 + entry * 12 + 4)? as usize;
+        (data.get(block..block + 4)? == tag).then_some(block)
+    })
+}
+
+fn ticks_to_duration(ticks: u64) -> Duration {
+    Duration::from_micros(ticks * 1_000_000 / TICKS_PER_SECOND)
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?))
+}
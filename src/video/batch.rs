@@ -0,0 +1,411 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use glob::{MatchOptions, Pattern};
+
+use super::journal::{JobState, Journal, JOURNAL_FILE_NAME};
+use super::options::numbered_paths;
+use super::segments::join_segments;
+use super::{
+    convert_segments_to, convert_segments_with_options, probe, Conversion, ConversionOptions, ConversionPlan,
+    ConvertError, OverwritePolicy, ProgressEvent, ProgressEventCallback,
+};
+
+/// Extensions of the camera and legacy formats converted by default.
+pub const INPUT_EXTENSIONS: [&str; 11] = ["mts", "m2ts", "ts", "mod", "tod", "avi", "mov", "mkv", "vob", "3gp", "wmv"];
+
+/// Settings for converting a whole directory.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub conversion: ConversionOptions,
+    /// How many files are converted at the same time.
+    pub workers: usize,
+    /// Keep converting the remaining files after one fails instead of returning its error.
+    pub continue_on_error: bool,
+    /// Also convert files in subdirectories, mirroring their layout under the output directory.
+    pub recursive: bool,
+    /// Only convert files matching one of these patterns (all files when empty).
+    pub include: Vec<Pattern>,
+    /// Never convert files matching any of these patterns.
+    pub exclude: Vec<Pattern>,
+    /// Extensions, without the dot, of the files to convert; matched case-insensitively.
+    pub extensions: Vec<String>,
+    /// Also convert files with other extensions when ffprobe recognises them as video.
+    pub detect_by_content: bool,
+    /// Skip files the journal of an earlier run records as converted and unchanged since.
+    pub resume: bool,
+    /// Join recordings the camera split into consecutive files into a single output.
+    pub join_segments: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            conversion: ConversionOptions::default(),
+            workers: default_workers(),
+            continue_on_error: false,
+            recursive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            extensions: INPUT_EXTENSIONS.iter().map(|ext| ext.to_string()).collect(),
+            detect_by_content: false,
+            resume: false,
+            join_segments: true,
+        }
+    }
+}
+
+// Stream-copy remuxing is I/O bound, so one ffmpeg per core keeps the disk busy
+pub fn default_workers() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+#[derive(Debug)]
+pub enum FileOutcome {
+    Converted { output: PathBuf, output_size: u64, plan: ConversionPlan },
+    Skipped { reason: String },
+    Failed { error: ConvertError },
+    /// Interrupted, or never started, because the batch was cancelled.
+    Cancelled,
+}
+
+#[derive(Debug)]
+pub struct BatchEntry {
+    pub input: PathBuf,
+    /// The later segments of a split recording that were joined onto `input`.
+    pub joined: Vec<PathBuf>,
+    pub outcome: FileOutcome,
+    pub duration: Duration,
+}
+
+/// What happened to every input of a directory conversion, in input order.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub entries: Vec<BatchEntry>,
+}
+
+impl BatchReport {
+    pub fn converted(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Converted { .. }))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Skipped { .. }))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Failed { .. }))
+    }
+
+    pub fn cancelled(&self) -> usize {
+        self.count(|outcome| matches!(outcome, FileOutcome::Cancelled))
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.failed() > 0
+    }
+
+    fn count(&self, predicate: impl Fn(&FileOutcome) -> bool) -> usize {
+        self.entries.iter().filter(|entry| predicate(&entry.outcome)).count()
+    }
+}
+
+pub fn convert_mts_files_in_directory_with_options(
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    options: &BatchOptions,
+    event_callback: Option<ProgressEventCallback>
+) -> Result<BatchReport, ConvertError> {
+    let mut files = collect_inputs(input_dir, output_dir, options)?;
+    if options.join_segments {
+        files = join_segments(files);
+    }
+    if files.is_empty() {
+        return Ok(BatchReport::default());
+    }
+    plan_outputs(&mut files, &options.conversion);
+
+    let journal_dir = output_dir.unwrap_or(input_dir);
+    fs::create_dir_all(journal_dir).map_err(|e| ConvertError::io(journal_dir, e))?;
+    let journal_path = journal_dir.join(JOURNAL_FILE_NAME);
+    let journal = if options.resume { Journal::load(&journal_path)? } else { Journal::new(&journal_path) };
+    let already_converted: Vec<bool> = files
+        .iter()
+        .map(|input| options.resume && journal.is_completed(&input.key, &input.path, &input.segments))
+        .collect();
+    let journal = Mutex::new(journal);
+
+    let progress = BatchProgress::new(files.len(), event_callback);
+    let next_file = AtomicUsize::new(0);
+    let entries = Mutex::new((0..files.len()).map(|_| None).collect::<Vec<_>>());
+    let stop = AtomicBool::new(false);
+    let workers = options.workers.clamp(1, files.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if stop.load(Ordering::SeqCst) || options.conversion.cancel.is_cancelled() {
+                    break;
+                }
+                let index = next_file.fetch_add(1, Ordering::SeqCst);
+                let Some(input) = files.get(index) else {
+                    break;
+                };
+
+                if already_converted[index] {
+                    progress.complete(index);
+                    entries.lock().unwrap()[index] = Some(BatchEntry {
+                        input: input.path.clone(),
+                        joined: input.segments.clone(),
+                        outcome: FileOutcome::Skipped { reason: String::from("already converted by an earlier run") },
+                        duration: Duration::ZERO,
+                    });
+                    continue;
+                }
+
+                let started = Instant::now();
+                record(&journal, input, JobState::InProgress);
+                let file_callback = progress.file_callback(index);
+                let result = convert_input(input, &options.conversion, file_callback);
+                match &result {
+                    Ok(conversion) => {
+                        record(&journal, input, JobState::Completed { output: conversion.output().to_path_buf() })
+                    }
+                    // Left in progress so a resumed run retries it
+                    Err(ConvertError::Cancelled) => {}
+                    Err(error) => record(&journal, input, JobState::Failed { error: error.to_string() }),
+                }
+                let outcome = match result {
+                    Ok(Conversion::Converted { output, plan }) => FileOutcome::Converted {
+                        output_size: fs::metadata(&output).map(|meta| meta.len()).unwrap_or(0),
+                        output,
+                        plan,
+                    },
+                    Ok(Conversion::Skipped(output)) => FileOutcome::Skipped {
+                        reason: format!("output already exists: {}", output.display()),
+                    },
+                    Err(ConvertError::Cancelled) => FileOutcome::Cancelled,
+                    Err(error) => {
+                        if !options.continue_on_error {
+                            stop.store(true, Ordering::SeqCst);
+                        }
+                        FileOutcome::Failed { error }
+                    }
+                };
+                entries.lock().unwrap()[index] = Some(BatchEntry {
+                    input: input.path.clone(),
+                    joined: input.segments.clone(),
+                    outcome,
+                    duration: started.elapsed(),
+                });
+            });
+        }
+    });
+
+    let cancelled = options.conversion.cancel.is_cancelled();
+    let mut report = BatchReport::default();
+    for (entry, input) in entries.into_inner().unwrap().into_iter().zip(&files) {
+        let entry = entry.unwrap_or_else(|| BatchEntry {
+            input: input.path.clone(),
+            joined: input.segments.clone(),
+            outcome: if cancelled {
+                FileOutcome::Cancelled
+            } else {
+                FileOutcome::Skipped { reason: String::from("batch stopped after an earlier failure") }
+            },
+            duration: Duration::ZERO,
+        });
+        report.entries.push(entry);
+    }
+
+    if !options.continue_on_error {
+        let first_failure = report.entries.iter().position(|entry| matches!(entry.outcome, FileOutcome::Failed { .. }));
+        if let Some(index) = first_failure {
+            if let FileOutcome::Failed { error } = report.entries.swap_remove(index).outcome {
+                return Err(error);
+            }
+        }
+    }
+    Ok(report)
+}
+
+pub(crate) struct BatchInput {
+    pub(crate) path: PathBuf,
+    // Identifies the file in the journal: its path relative to the input directory
+    pub(crate) key: String,
+    // Where this file's output goes; `None` writes it next to the input
+    pub(crate) output_dir: Option<PathBuf>,
+    // Later segments of the same recording, converted together with `path`
+    pub(crate) segments: Vec<PathBuf>,
+    // Where the output goes, once the batch has named every output
+    pub(crate) output_path: Option<PathBuf>,
+}
+
+impl BatchInput {
+    pub(crate) fn last_segment(&self) -> &Path {
+        self.segments.last().unwrap_or(&self.path)
+    }
+}
+
+fn convert_input(
+    input: &BatchInput,
+    options: &ConversionOptions,
+    event_callback: Option<ProgressEventCallback>,
+) -> Result<Conversion, ConvertError> {
+    let segments: Vec<PathBuf> = std::iter::once(&input.path).chain(&input.segments).cloned().collect();
+    match input.output_path {
+        Some(ref output_path) => convert_segments_to(&segments, output_path, options, event_callback),
+        None => convert_segments_with_options(&segments, input.output_dir.as_deref(), options, event_callback),
+    }
+}
+
+// Every output is named before any conversion starts, so inputs whose names collide are told apart
+// here instead of racing each other for the same file. Later inputs get the numbered names `rename`
+// would give them; under `rename`, files already on disk are also avoided up front.
+fn plan_outputs(files: &mut [BatchInput], options: &ConversionOptions) {
+    // Compared regardless of case, which is how Windows and macOS compare names
+    let key = |path: &Path| path.to_string_lossy().to_lowercase();
+    let mut taken = HashSet::new();
+    for (index, input) in files.iter_mut().enumerate() {
+        let output_dir = input.output_dir.as_deref().unwrap_or_else(|| input.path.parent().unwrap());
+        let name = options.output_name.render(&input.path, index + 1, options.format.extension());
+        let planned = output_dir.join(name);
+        let is_taken = |path: &Path| {
+            taken.contains(&key(path)) || (options.overwrite == OverwritePolicy::Rename && path.exists())
+        };
+        let output_path = if !is_taken(&planned) {
+            planned
+        } else {
+            let renamed = numbered_paths(&planned).find(|candidate| !is_taken(candidate)).unwrap();
+            if taken.contains(&key(&planned)) {
+                println!(
+                    "{} would also be converted to {}, so it goes to {}",
+                    input.path.display(),
+                    planned.display(),
+                    renamed.display()
+                );
+            }
+            renamed
+        };
+        taken.insert(key(&output_path));
+        input.output_path = Some(output_path);
+    }
+}
+
+// A journal that cannot be written only costs the ability to resume, so the batch carries on
+fn record(journal: &Mutex<Journal>, input: &BatchInput, state: JobState) {
+    let mut journal = journal.lock().unwrap();
+    if let Err(e) = journal.record(&input.key, &input.path, &input.segments, state) {
+        println!("Warning: could not update the batch journal {}: {}", journal.path().display(), e);
+    }
+}
+
+pub(crate) fn collect_inputs(
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    options: &BatchOptions,
+) -> Result<Vec<BatchInput>, ConvertError> {
+    // Outputs of an earlier run are not inputs, even in a format cameras record in too
+    let journal_dir = output_dir.unwrap_or(input_dir);
+    let earlier_outputs: HashSet<PathBuf> = Journal::load(&journal_dir.join(JOURNAL_FILE_NAME))
+        .map(|journal| journal.completed_outputs().map(Path::to_path_buf).collect())
+        .unwrap_or_default();
+    let separate_output_dir = output_dir.filter(|output_dir| *output_dir != input_dir);
+
+    let mut files = Vec::new();
+    let mut pending = vec![input_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir).map_err(|e| ConvertError::io(&dir, e))? {
+            let entry = entry.map_err(|e| ConvertError::io(&dir, e))?;
+            let path = entry.path();
+            // `file_type` does not follow symlinks, so linked directories cannot cause loops
+            let file_type = entry.file_type().map_err(|e| ConvertError::io(&path, e))?;
+            if file_type.is_dir() {
+                // An output directory inside the input directory only holds outputs
+                if options.recursive && separate_output_dir != Some(path.as_path()) {
+                    pending.push(path);
+                }
+                continue;
+            }
+
+            let relative = path.strip_prefix(input_dir).unwrap_or(&path);
+            if is_selected(relative, options) && !earlier_outputs.contains(&path) && is_input(&path, options) {
+                let output_dir = output_dir.map(|out| match relative.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => out.join(parent),
+                    _ => out.to_path_buf(),
+                });
+                let key = relative.to_string_lossy().replace('\\', "/");
+                files.push(BatchInput { path, key, output_dir, segments: Vec::new(), output_path: None });
+            }
+        }
+    }
+    // Sorted so file indices are stable and match the camera's numbering
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn is_input(path: &Path, options: &BatchOptions) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    // Hidden files include our own temporary outputs
+    if path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.')) {
+        return false;
+    }
+    if options.extensions.iter().any(|allowed| allowed.eq_ignore_ascii_case(extension)) {
+        return true;
+    }
+    options.detect_by_content && probe(path).is_ok_and(|info| info.is_recording())
+}
+
+// Patterns are matched case-insensitively against the path relative to the input directory,
+// so `*.MTS` matches at the top level and `**/STREAM/*.MTS` at any depth
+fn is_selected(relative: &Path, options: &BatchOptions) -> bool {
+    let match_options = MatchOptions {
+        case_sensitive: false,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    let matches = |pattern: &Pattern| pattern.matches_path_with(relative, match_options);
+    (options.include.is_empty() || options.include.iter().any(matches)) && !options.exclude.iter().any(matches)
+}
+
+// Combines the progress of files that run concurrently and may finish in any order
+struct BatchProgress {
+    callback: Option<ProgressEventCallback>,
+    per_file: Arc<Mutex<Vec<f32>>>,
+}
+
+impl BatchProgress {
+    fn new(file_count: usize, callback: Option<ProgressEventCallback>) -> Self {
+        BatchProgress {
+            callback,
+            per_file: Arc::new(Mutex::new(vec![0.0; file_count])),
+        }
+    }
+
+    // Counts a file that needs no work as done
+    fn complete(&self, index: usize) {
+        self.per_file.lock().unwrap()[index] = 1.0;
+    }
+
+    // Re-scopes a file's events to its position in the batch
+    fn file_callback(&self, index: usize) -> Option<ProgressEventCallback> {
+        let callback = Arc::clone(self.callback.as_ref()?);
+        let per_file = Arc::clone(&self.per_file);
+        Some(Arc::new(move |event: &ProgressEvent| {
+            // Holding the lock while calling back keeps overall progress monotonic for the receiver
+            let mut per_file = per_file.lock().unwrap();
+            per_file[index] = per_file[index].max(event.progress);
+            let total = per_file.len();
+            let mut event = event.clone();
+            event.file_index = index;
+            event.file_count = total;
+            event.overall_progress = (per_file.iter().sum::<f32>() / total as f32).min(1.0);
+            callback(&event);
+        }))
+    }
+}
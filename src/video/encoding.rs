@@ -0,0 +1,292 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::format::OutputFormat;
+
+// EBU R128 loudness normalization to -23 LUFS. loudnorm resamples to 192 kHz as it works,
+// so the result is brought back to the 48 kHz cameras record.
+pub(crate) const LOUDNESS_FILTER: &str = "loudnorm=I=-23:LRA=7:TP=-2,aresample=48000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoCodec {
+    H264,
+    H265,
+    Vp9,
+    /// AV1 through libaom, the reference encoder.
+    Av1,
+    /// AV1 through SVT-AV1, which is much faster than libaom.
+    Av1Svt,
+}
+
+impl VideoCodec {
+    pub const ALL: [VideoCodec; 5] =
+        [VideoCodec::H264, VideoCodec::H265, VideoCodec::Vp9, VideoCodec::Av1, VideoCodec::Av1Svt];
+
+    /// The ffmpeg encoder used for this codec.
+    pub fn encoder(self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::H265 => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::Av1 => "libaom-av1",
+            VideoCodec::Av1Svt => "libsvtav1",
+        }
+    }
+
+    /// The codec's name as ffprobe reports it.
+    pub fn codec_name(self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::H265 => "hevc",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::Av1 | VideoCodec::Av1Svt => "av1",
+        }
+    }
+
+    // libvpx and libaom call their speed setting `cpu-used` rather than `preset`
+    fn preset_option(self) -> &'static str {
+        match self {
+            VideoCodec::Vp9 | VideoCodec::Av1 => "-cpu-used",
+            VideoCodec::H264 | VideoCodec::H265 | VideoCodec::Av1Svt => "-preset",
+        }
+    }
+}
+
+impl fmt::Display for VideoCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::H265 => "h265",
+            VideoCodec::Vp9 => "vp9",
+            VideoCodec::Av1 => "av1",
+            VideoCodec::Av1Svt => "av1-svt",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for VideoCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        let aliases = match s.as_str() {
+            "x264" | "libx264" | "avc" => "h264",
+            "x265" | "libx265" | "hevc" => "h265",
+            "libvpx-vp9" => "vp9",
+            "libaom-av1" | "aom" => "av1",
+            "svt-av1" | "libsvtav1" | "svt" => "av1-svt",
+            other => other,
+        };
+        VideoCodec::ALL
+            .into_iter()
+            .find(|codec| codec.to_string() == aliases)
+            .ok_or_else(|| format!("unknown video codec '{}' (expected h264, h265, vp9, av1 or av1-svt)", s))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AudioCodec {
+    Aac,
+    Opus,
+    Mp3,
+}
+
+impl AudioCodec {
+    pub const ALL: [AudioCodec; 3] = [AudioCodec::Aac, AudioCodec::Opus, AudioCodec::Mp3];
+
+    pub fn encoder(self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "libopus",
+            AudioCodec::Mp3 => "libmp3lame",
+        }
+    }
+
+    /// The codec's name as ffprobe reports it.
+    pub fn codec_name(self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "opus",
+            AudioCodec::Mp3 => "mp3",
+        }
+    }
+}
+
+impl fmt::Display for AudioCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Opus => "opus",
+            AudioCodec::Mp3 => "mp3",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for AudioCodec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AudioCodec::ALL
+            .into_iter()
+            .find(|codec| codec.to_string().eq_ignore_ascii_case(s) || codec.encoder().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown audio codec '{}' (expected aac, opus or mp3)", s))
+    }
+}
+
+/// How the video encoder trades size for quality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    /// Constant quality; lower is better and larger.
+    Crf(u8),
+    /// Average bitrate in kbit/s.
+    Bitrate(u32),
+}
+
+/// Re-encodes every video stream with these settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoSettings {
+    pub codec: VideoCodec,
+    pub quality: Quality,
+    /// Encoder speed preset, e.g. `medium` for x264 or `6` for libaom.
+    pub preset: Option<String>,
+    /// Scales taller video down to this height, keeping the aspect ratio.
+    pub max_height: Option<u32>,
+    pub fps: Option<f64>,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        VideoSettings { codec: VideoCodec::H264, quality: Quality::Crf(23), preset: None, max_height: None, fps: None }
+    }
+}
+
+impl VideoSettings {
+    /// Encoder arguments for the output stream selected by `spec`, e.g. `0` or `v`.
+    pub(crate) fn args(&self, spec: &str, format: OutputFormat) -> Vec<String> {
+        let mut args = vec![format!("-c:{}", spec), self.codec.encoder().to_string()];
+        match self.quality {
+            Quality::Crf(crf) => {
+                args.extend([format!("-crf:{}", spec), crf.to_string()]);
+                // libvpx and libaom only honour the CRF as a constant quality with no target bitrate
+                if matches!(self.codec, VideoCodec::Vp9 | VideoCodec::Av1) {
+                    args.extend([format!("-b:{}", spec), String::from("0")]);
+                }
+            }
+            Quality::Bitrate(kbps) => args.extend([format!("-b:{}", spec), format!("{}k", kbps)]),
+        }
+        if let Some(ref preset) = self.preset {
+            args.extend([format!("{}:{}", self.codec.preset_option(), spec), preset.clone()]);
+        }
+        let mut filters = Vec::new();
+        if let Some(height) = self.max_height {
+            filters.push(format!("scale=-2:'min({},ih)'", height));
+        }
+        if let Some(fps) = self.fps {
+            filters.push(format!("fps={}", fps));
+        }
+        if !filters.is_empty() {
+            args.extend([format!("-filter:{}", spec), filters.join(",")]);
+        }
+        // Apple players only recognise HEVC in MP4 under the `hvc1` tag
+        if self.codec == VideoCodec::H265 && format.is_isobmff() {
+            args.extend([format!("-tag:{}", spec), String::from("hvc1")]);
+        }
+        args
+    }
+}
+
+/// Re-encodes every audio stream with these settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioSettings {
+    pub codec: AudioCodec,
+    /// Bitrate in kbit/s; the encoder's default when `None`.
+    pub bitrate_kbps: Option<u32>,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings { codec: AudioCodec::Aac, bitrate_kbps: None }
+    }
+}
+
+impl AudioSettings {
+    pub(crate) fn args(&self, spec: &str) -> Vec<String> {
+        let mut args = vec![format!("-c:{}", spec), self.codec.encoder().to_string()];
+        if let Some(kbps) = self.bitrate_kbps {
+            args.extend([format!("-b:{}", spec), format!("{}k", kbps)]);
+        }
+        args
+    }
+}
+
+/// Forces re-encoding with specific settings; streams without settings follow the stream plan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncodingSettings {
+    pub video: Option<VideoSettings>,
+    pub audio: Option<AudioSettings>,
+}
+
+/// Named combinations of stream handling and encoding settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// Copies every stream untouched, for keeping the camera's original quality.
+    ArchiveCopy,
+    /// H.264 at 720p with AAC, small enough for messaging apps.
+    Share720p,
+    /// Heavily compressed 480p H.264 for embedding on web pages.
+    WebSmall,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 3] = [Preset::ArchiveCopy, Preset::Share720p, Preset::WebSmall];
+
+    pub fn encoding(self) -> EncodingSettings {
+        match self {
+            Preset::ArchiveCopy => EncodingSettings::default(),
+            Preset::Share720p => EncodingSettings {
+                video: Some(VideoSettings {
+                    codec: VideoCodec::H264,
+                    quality: Quality::Crf(23),
+                    preset: Some(String::from("medium")),
+                    max_height: Some(720),
+                    fps: None,
+                }),
+                audio: Some(AudioSettings { codec: AudioCodec::Aac, bitrate_kbps: Some(128) }),
+            },
+            Preset::WebSmall => EncodingSettings {
+                video: Some(VideoSettings {
+                    codec: VideoCodec::H264,
+                    quality: Quality::Crf(28),
+                    preset: Some(String::from("slow")),
+                    max_height: Some(480),
+                    fps: Some(30.0),
+                }),
+                audio: Some(AudioSettings { codec: AudioCodec::Aac, bitrate_kbps: Some(96) }),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Preset::ArchiveCopy => "archive-copy",
+            Preset::Share720p => "share-720p",
+            Preset::WebSmall => "web-small",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown preset '{}' (expected archive-copy, share-720p or web-small)", s))
+    }
+}
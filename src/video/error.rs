@@ -0,0 +1,176 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::time::Duration;
+
+use super::format::OutputFormat;
+use super::trim::format_timestamp;
+
+/// Everything that can go wrong while converting, with enough context to tell the user why.
+#[derive(Debug)]
+pub enum ConvertError {
+    /// The `ffmpeg` executable could not be started.
+    FfmpegNotFound,
+    /// The `ffprobe` executable could not be started.
+    FfprobeNotFound,
+    InputNotFound(PathBuf),
+    OutputExists(PathBuf),
+    /// ffmpeg reported success but wrote nothing.
+    EmptyOutput(PathBuf),
+    /// ffmpeg reported success but what it wrote does not hold the converted recording, e.g. after a broken mux.
+    InvalidOutput {
+        path: PathBuf,
+        reason: String,
+    },
+    /// ffmpeg refused a stream because the codec cannot be decoded or stored in the output.
+    UnsupportedCodec {
+        path: PathBuf,
+        codec: Option<String>,
+        stderr_tail: String,
+    },
+    /// The requested codec cannot be stored in the chosen output format.
+    IncompatibleCodec {
+        codec: String,
+        format: OutputFormat,
+    },
+    /// None of the input's streams can be stored in the chosen output format, e.g. no audio to extract.
+    NoUsableStreams {
+        path: PathBuf,
+        format: OutputFormat,
+    },
+    /// The requested time range starts after the clip ends.
+    TrimOutOfRange {
+        path: PathBuf,
+        start: Duration,
+        duration: Duration,
+    },
+    FfmpegFailed {
+        path: PathBuf,
+        status: ExitStatus,
+        stderr_tail: String,
+    },
+    /// ffprobe could not read the input.
+    ProbeFailed {
+        path: PathBuf,
+        stderr_tail: String,
+    },
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// The conversion was stopped through its `CancellationToken`.
+    Cancelled,
+}
+
+impl ConvertError {
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self {
+        ConvertError::Io { path: path.to_path_buf(), source }
+    }
+
+    pub(crate) fn spawn(path: &Path, source: io::Error) -> Self {
+        if source.kind() == io::ErrorKind::NotFound {
+            ConvertError::FfmpegNotFound
+        } else {
+            ConvertError::io(path, source)
+        }
+    }
+
+    // Works out why ffmpeg exited unsuccessfully from the tail of its stderr
+    pub(crate) fn from_ffmpeg_exit(input: &Path, output: &Path, status: ExitStatus, stderr_tail: String) -> Self {
+        if stderr_tail.contains("already exists") {
+            return ConvertError::OutputExists(output.to_path_buf());
+        }
+
+        let unsupported = ["Could not find tag for codec", "not currently supported in container", "Unsupported codec"]
+            .iter()
+            .any(|marker| stderr_tail.contains(marker));
+        if unsupported {
+            let codec = stderr_tail
+                .split("for codec ")
+                .nth(1)
+                .and_then(|rest| rest.split_whitespace().next())
+                .map(|codec| codec.trim_matches(|c: char| !c.is_alphanumeric() && c != '_').to_string())
+                .filter(|codec| !codec.is_empty());
+            return ConvertError::UnsupportedCodec { path: input.to_path_buf(), codec, stderr_tail };
+        }
+
+        ConvertError::FfmpegFailed { path: input.to_path_buf(), status, stderr_tail }
+    }
+
+    /// The last lines ffmpeg wrote to stderr, when the error came from ffmpeg.
+    pub fn stderr_tail(&self) -> Option<&str> {
+        match self {
+            ConvertError::UnsupportedCodec { stderr_tail, .. }
+            | ConvertError::FfmpegFailed { stderr_tail, .. }
+            | ConvertError::ProbeFailed { stderr_tail, .. } => Some(stderr_tail),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::FfmpegNotFound => {
+                write!(f, "ffmpeg was not found; install FFmpeg and make sure it is on your PATH")
+            }
+            ConvertError::FfprobeNotFound => {
+                write!(f, "ffprobe was not found; install FFmpeg and make sure it is on your PATH")
+            }
+            ConvertError::InputNotFound(path) => write!(f, "input not found: {}", path.display()),
+            ConvertError::OutputExists(path) => write!(f, "output already exists: {}", path.display()),
+            ConvertError::EmptyOutput(path) => write!(f, "ffmpeg produced an empty file for {}", path.display()),
+            ConvertError::InvalidOutput { path, reason } => {
+                write!(f, "ffmpeg produced an unusable file for {}: {}", path.display(), reason)
+            }
+            ConvertError::UnsupportedCodec { path, codec: Some(codec), .. } => {
+                write!(f, "unsupported codec {} in {}", codec, path.display())
+            }
+            ConvertError::UnsupportedCodec { path, codec: None, .. } => {
+                write!(f, "unsupported codec in {}", path.display())
+            }
+            ConvertError::IncompatibleCodec { codec, format } => {
+                write!(f, "{} cannot be stored in {} files", codec, format.description())
+            }
+            ConvertError::NoUsableStreams { path, format } => {
+                write!(f, "{} has no streams that can be stored in {} files", path.display(), format.description())
+            }
+            ConvertError::TrimOutOfRange { path, start, duration } => write!(
+                f,
+                "{} is only {} long, so it cannot be trimmed from {}",
+                path.display(),
+                format_timestamp(*duration),
+                format_timestamp(*start)
+            ),
+            ConvertError::FfmpegFailed { path, status, stderr_tail } => {
+                match status.code() {
+                    Some(code) => write!(f, "ffmpeg exited with code {} while converting {}", code, path.display())?,
+                    None => write!(f, "ffmpeg was terminated while converting {}", path.display())?,
+                }
+                match stderr_tail.lines().rev().find(|line| !line.trim().is_empty()) {
+                    Some(line) => write!(f, ": {}", line.trim()),
+                    None => Ok(()),
+                }
+            }
+            ConvertError::ProbeFailed { path, stderr_tail } => {
+                write!(f, "ffprobe could not read {}", path.display())?;
+                match stderr_tail.lines().rev().find(|line| !line.trim().is_empty()) {
+                    Some(line) => write!(f, ": {}", line.trim()),
+                    None => Ok(()),
+                }
+            }
+            ConvertError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ConvertError::Cancelled => write!(f, "conversion cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConvertError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
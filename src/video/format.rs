@@ -0,0 +1,241 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::encoding::EncodingSettings;
+use super::error::ConvertError;
+use super::probe::StreamKind;
+
+/// The container conversions write.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    #[default]
+    Mp4,
+    /// MP4 written as a series of self-contained fragments, playable while it is still being written.
+    FragmentedMp4,
+    Mkv,
+    Mov,
+    Webm,
+    /// AAC audio only, in an MP4 container.
+    M4a,
+    Mp3,
+    Flac,
+    Wav,
+    /// Opus audio only, in an Ogg container.
+    Opus,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 10] = [
+        OutputFormat::Mp4,
+        OutputFormat::FragmentedMp4,
+        OutputFormat::Mkv,
+        OutputFormat::Mov,
+        OutputFormat::Webm,
+        OutputFormat::M4a,
+        OutputFormat::Mp3,
+        OutputFormat::Flac,
+        OutputFormat::Wav,
+        OutputFormat::Opus,
+    ];
+
+    /// The output file extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Mp4 | OutputFormat::FragmentedMp4 => "mp4",
+            OutputFormat::Mkv => "mkv",
+            OutputFormat::Mov => "mov",
+            OutputFormat::Webm => "webm",
+            OutputFormat::M4a => "m4a",
+            OutputFormat::Mp3 => "mp3",
+            OutputFormat::Flac => "flac",
+            OutputFormat::Wav => "wav",
+            OutputFormat::Opus => "opus",
+        }
+    }
+
+    /// The container's common name, for messages.
+    pub fn description(self) -> &'static str {
+        match self {
+            OutputFormat::Mp4 => "MP4",
+            OutputFormat::FragmentedMp4 => "fragmented MP4",
+            OutputFormat::Mkv => "Matroska",
+            OutputFormat::Mov => "QuickTime",
+            OutputFormat::Webm => "WebM",
+            OutputFormat::M4a => "M4A",
+            OutputFormat::Mp3 => "MP3",
+            OutputFormat::Flac => "FLAC",
+            OutputFormat::Wav => "WAV",
+            OutputFormat::Opus => "Opus",
+        }
+    }
+
+    /// Whether the format only holds audio, so converting to it extracts the audio tracks.
+    pub fn is_audio_only(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::M4a | OutputFormat::Mp3 | OutputFormat::Flac | OutputFormat::Wav | OutputFormat::Opus
+        )
+    }
+
+    /// Whether the container holds a single stream, so only the first audio track is extracted.
+    pub fn holds_single_stream(self) -> bool {
+        matches!(self, OutputFormat::Mp3 | OutputFormat::Flac | OutputFormat::Wav)
+    }
+
+    /// The ffmpeg muxer and its options; ffmpeg cannot infer them from the temporary file's name.
+    pub(crate) fn muxer_args(self) -> &'static [&'static str] {
+        match self {
+            OutputFormat::Mp4 => &["-f", "mp4"],
+            OutputFormat::FragmentedMp4 => &["-f", "mp4", "-movflags", "+frag_keyframe+empty_moov+default_base_moof"],
+            OutputFormat::Mkv => &["-f", "matroska"],
+            OutputFormat::Mov => &["-f", "mov"],
+            OutputFormat::Webm => &["-f", "webm"],
+            OutputFormat::M4a => &["-f", "ipod"],
+            OutputFormat::Mp3 => &["-f", "mp3"],
+            OutputFormat::Flac => &["-f", "flac"],
+            OutputFormat::Wav => &["-f", "wav"],
+            OutputFormat::Opus => &["-f", "opus"],
+        }
+    }
+
+    // Codecs the container's usual players accept
+    fn playable_codecs(self, kind: StreamKind) -> &'static [&'static str] {
+        match (self, kind) {
+            (OutputFormat::Mp4 | OutputFormat::FragmentedMp4, StreamKind::Video) => {
+                &["h264", "hevc", "mpeg4", "av1", "vp9"]
+            }
+            (OutputFormat::Mp4 | OutputFormat::FragmentedMp4, StreamKind::Audio) => &["aac", "mp3", "alac"],
+            (OutputFormat::Mp4 | OutputFormat::FragmentedMp4 | OutputFormat::Mov, StreamKind::Subtitle) => {
+                &["mov_text"]
+            }
+            (OutputFormat::Mov, StreamKind::Video) => &["h264", "hevc", "mpeg4", "prores", "mjpeg"],
+            (OutputFormat::Mov, StreamKind::Audio) => {
+                &["aac", "alac", "mp3", "pcm_s16le", "pcm_s16be", "pcm_s24le", "pcm_s24be"]
+            }
+            (OutputFormat::Mkv, StreamKind::Video) => &[
+                "h264", "hevc", "mpeg1video", "mpeg2video", "mpeg4", "vc1", "vp8", "vp9", "av1", "prores", "mjpeg",
+                "dvvideo", "ffv1",
+            ],
+            (OutputFormat::Mkv, StreamKind::Audio) => &[
+                "aac", "ac3", "eac3", "dts", "truehd", "mp2", "mp3", "flac", "alac", "opus", "vorbis", "pcm_s16le",
+                "pcm_s24le", "pcm_s32le", "pcm_f32le",
+            ],
+            (OutputFormat::Mkv, StreamKind::Subtitle) => {
+                &["subrip", "ass", "ssa", "webvtt", "hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle"]
+            }
+            (OutputFormat::Webm, StreamKind::Video) => &["vp8", "vp9", "av1"],
+            (OutputFormat::Webm, StreamKind::Audio) => &["opus", "vorbis"],
+            (OutputFormat::Webm, StreamKind::Subtitle) => &["webvtt"],
+            (OutputFormat::M4a, StreamKind::Audio) => &["aac", "alac"],
+            (OutputFormat::Mp3, StreamKind::Audio) => &["mp3"],
+            (OutputFormat::Flac, StreamKind::Audio) => &["flac"],
+            (OutputFormat::Wav, StreamKind::Audio) => &["pcm_s16le", "pcm_s24le", "pcm_s32le", "pcm_f32le", "pcm_u8"],
+            (OutputFormat::Opus, StreamKind::Audio) => &["opus"],
+            _ => &[],
+        }
+    }
+
+    // Codecs the muxer accepts even though many players reject them
+    fn other_codecs(self, kind: StreamKind) -> &'static [&'static str] {
+        match (self, kind) {
+            (OutputFormat::Mp4 | OutputFormat::FragmentedMp4, StreamKind::Video) => {
+                &["mpeg1video", "mpeg2video", "mjpeg"]
+            }
+            (OutputFormat::Mp4 | OutputFormat::FragmentedMp4, StreamKind::Audio) => {
+                &["ac3", "eac3", "mp2", "opus", "flac"]
+            }
+            (OutputFormat::Mov, StreamKind::Video) => &["mpeg2video", "dvvideo"],
+            (OutputFormat::Mov, StreamKind::Audio) => &["ac3", "eac3"],
+            _ => &[],
+        }
+    }
+
+    /// Whether players of this container generally accept `codec`.
+    pub fn plays(self, kind: StreamKind, codec: &str) -> bool {
+        self.playable_codecs(kind).contains(&codec)
+    }
+
+    /// Whether the container can store `codec` at all.
+    pub fn can_hold(self, kind: StreamKind, codec: &str) -> bool {
+        self.plays(kind, codec) || self.other_codecs(kind).contains(&codec)
+    }
+
+    /// The encoder for streams that have to be re-encoded without explicit settings.
+    pub(crate) fn default_encoder(self, kind: StreamKind) -> Option<&'static str> {
+        match (self, kind) {
+            (format, StreamKind::Video | StreamKind::Subtitle) if format.is_audio_only() => None,
+            (OutputFormat::M4a, StreamKind::Audio) => Some("aac"),
+            (OutputFormat::Mp3, StreamKind::Audio) => Some("libmp3lame"),
+            (OutputFormat::Flac, StreamKind::Audio) => Some("flac"),
+            (OutputFormat::Wav, StreamKind::Audio) => Some("pcm_s16le"),
+            (OutputFormat::Webm | OutputFormat::Opus, StreamKind::Audio) => Some("libopus"),
+            (OutputFormat::Webm, StreamKind::Video) => Some("libvpx-vp9"),
+            (OutputFormat::Webm, StreamKind::Subtitle) => Some("webvtt"),
+            (_, StreamKind::Video) => Some("libx264"),
+            (_, StreamKind::Audio) => Some("aac"),
+            (OutputFormat::Mkv, StreamKind::Subtitle) => Some("srt"),
+            (_, StreamKind::Subtitle) => Some("mov_text"),
+            (_, StreamKind::Data | StreamKind::Other) => None,
+        }
+    }
+
+    /// Whether the container follows the MP4 family's conventions, such as the `hvc1` tag for HEVC.
+    pub(crate) fn is_isobmff(self) -> bool {
+        matches!(self, OutputFormat::Mp4 | OutputFormat::FragmentedMp4 | OutputFormat::Mov)
+    }
+
+    /// Checks the codecs `encoding` asks for can be stored in this container.
+    pub fn check_encoding(self, encoding: &EncodingSettings) -> Result<(), ConvertError> {
+        let requested = [
+            encoding.video.as_ref().map(|video| (StreamKind::Video, video.codec.codec_name())),
+            encoding.audio.as_ref().map(|audio| (StreamKind::Audio, audio.codec.codec_name())),
+        ];
+        for (kind, codec) in requested.into_iter().flatten() {
+            if !self.can_hold(kind, codec) {
+                return Err(ConvertError::IncompatibleCodec { codec: codec.to_string(), format: self });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Mp4 => "mp4",
+            OutputFormat::FragmentedMp4 => "fmp4",
+            OutputFormat::Mkv => "mkv",
+            OutputFormat::Mov => "mov",
+            OutputFormat::Webm => "webm",
+            OutputFormat::M4a => "m4a",
+            OutputFormat::Mp3 => "mp3",
+            OutputFormat::Flac => "flac",
+            OutputFormat::Wav => "wav",
+            OutputFormat::Opus => "opus",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        let name = match s.as_str() {
+            "fragmented-mp4" | "fragmented" => "fmp4",
+            "matroska" => "mkv",
+            "quicktime" => "mov",
+            "aac" => "m4a",
+            "wave" => "wav",
+            other => other,
+        };
+        OutputFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string() == name)
+            .ok_or_else(|| {
+                let expected = "mp4, fmp4, mkv, mov, webm, m4a, mp3, flac, wav or opus";
+                format!("unknown output format '{}' (expected {})", s, expected)
+            })
+    }
+}
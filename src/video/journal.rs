@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use super::error::ConvertError;
+
+/// Name of the journal a batch keeps in its output directory.
+pub const JOURNAL_FILE_NAME: &str = ".magic-converter-journal.json";
+
+/// What an earlier run did with an input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobState {
+    /// The conversion started but the run ended before it finished.
+    InProgress,
+    Completed { output: PathBuf },
+    Failed { error: String },
+}
+
+/// The journal's record of one input, tied to the size and modification time it had.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub size: u64,
+    /// Milliseconds since the Unix epoch.
+    pub modified_ms: u64,
+    /// The size and modification time of each later segment joined onto the input.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub joined: Vec<SegmentFingerprint>,
+    #[serde(flatten)]
+    pub state: JobState,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentFingerprint {
+    pub size: u64,
+    /// Milliseconds since the Unix epoch.
+    pub modified_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JournalFile {
+    files: BTreeMap<String, JournalEntry>,
+}
+
+/// Per-file state of a batch run, saved after every change so an interrupted run can be resumed.
+///
+/// Inputs are keyed by their path relative to the batch's input directory.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    contents: JournalFile,
+}
+
+impl Journal {
+    /// Starts an empty journal that will be saved to `path`.
+    pub fn new(path: &Path) -> Self {
+        Journal { path: path.to_path_buf(), contents: JournalFile::default() }
+    }
+
+    /// Reads the journal at `path`, or starts an empty one if there is none yet.
+    pub fn load(path: &Path) -> Result<Self, ConvertError> {
+        let contents = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| ConvertError::io(path, io::Error::new(io::ErrorKind::InvalidData, e)))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => JournalFile::default(),
+            Err(e) => return Err(ConvertError::io(path, e)),
+        };
+        Ok(Journal { path: path.to_path_buf(), contents })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entry(&self, key: &str) -> Option<&JournalEntry> {
+        self.contents.files.get(key)
+    }
+
+    /// The outputs of every conversion the journal records as completed.
+    pub fn completed_outputs(&self) -> impl Iterator<Item = &Path> {
+        self.contents.files.values().filter_map(|entry| match &entry.state {
+            JobState::Completed { output } => Some(output.as_path()),
+            _ => None,
+        })
+    }
+
+    /// Whether `input`, with the `joined` segments of its recording, was converted by an earlier run, none of
+    /// them has changed since, and the output is still there.
+    pub fn is_completed(&self, key: &str, input: &Path, joined: &[PathBuf]) -> bool {
+        match self.entry(key) {
+            Some(entry @ JournalEntry { state: JobState::Completed { output }, .. }) => {
+                let unchanged = |path: &Path, recorded: SegmentFingerprint| fingerprint(path) == Some(recorded);
+                unchanged(input, SegmentFingerprint { size: entry.size, modified_ms: entry.modified_ms })
+                    && entry.joined.len() == joined.len()
+                    && joined.iter().zip(&entry.joined).all(|(segment, recorded)| unchanged(segment, *recorded))
+                    && output.is_file()
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the new state of `input`, and of the `joined` segments converted with it, and saves the journal.
+    pub fn record(&mut self, key: &str, input: &Path, joined: &[PathBuf], state: JobState) -> Result<(), ConvertError> {
+        let SegmentFingerprint { size, modified_ms } = fingerprint(input).unwrap_or_default();
+        let joined = joined.iter().map(|segment| fingerprint(segment).unwrap_or_default()).collect();
+        self.contents.files.insert(key.to_string(), JournalEntry { size, modified_ms, joined, state });
+        self.save()
+    }
+
+    // Written under a temporary name and renamed, so a crash mid-save never corrupts the journal
+    fn save(&self) -> Result<(), ConvertError> {
+        let json = serde_json::to_vec_pretty(&self.contents).expect("journal entries always serialize");
+        let temp = self.path.with_extension("json.part");
+        fs::write(&temp, json).map_err(|e| ConvertError::io(&temp, e))?;
+        fs::rename(&temp, &self.path).map_err(|e| ConvertError::io(&self.path, e))
+    }
+}
+
+fn fingerprint(input: &Path) -> Option<SegmentFingerprint> {
+    let metadata = fs::metadata(input).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(SegmentFingerprint { size: metadata.len(), modified_ms: modified.as_millis() as u64 })
+}
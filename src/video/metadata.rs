@@ -0,0 +1,132 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::avchd::RecordingTime;
+use super::probe::MediaInfo;
+
+/// Tags written into converted files on top of the metadata carried over from the input.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataTags {
+    pub title: Option<String>,
+    pub comment: Option<String>,
+    pub author: Option<String>,
+}
+
+/// When a recording was made: the container's `creation_time` tag, or else the input's modification
+/// time, which cameras set to the recording time and copying usually keeps.
+pub(crate) fn recording_time(input: &Path, info: Option<&MediaInfo>) -> Option<SystemTime> {
+    info.and_then(|info| info.creation_time.as_deref())
+        .and_then(parse_creation_time)
+        .or_else(|| fs::metadata(input).and_then(|meta| meta.modified()).ok())
+}
+
+// The input's own metadata is copied, then `creation_time` and any custom tags override it.
+// MP4 stores the creation time in its `mvhd` atom; `author` is written as both the `artist` most
+// players show and the QuickTime `author`.
+pub(crate) fn add_metadata_args(command: &mut Command, tags: &MetadataTags, created: Option<SystemTime>) {
+    command.args(["-map_metadata", "0"]);
+    if let Some(created) = created {
+        command.arg("-metadata").arg(format!("creation_time={}", format_creation_time(created)));
+    }
+    let custom =
+        [("title", &tags.title), ("comment", &tags.comment), ("artist", &tags.author), ("author", &tags.author)];
+    for (key, value) in custom {
+        if let Some(value) = value {
+            command.arg("-metadata").arg(format!("{}={}", key, value));
+        }
+    }
+}
+
+/// Sets the modification time of a finished output, so file managers and photo libraries sort it by
+/// when it was recorded rather than converted.
+pub(crate) fn set_modified(path: &Path, time: SystemTime) -> io::Result<()> {
+    fs::File::options().write(true).open(path)?.set_modified(time)
+}
+
+/// Parses ISO 8601 times as ffprobe reports them, e.g. `2023-07-14T09:30:12.000000Z`.
+///
+/// Times without an offset are taken as UTC, which is what MP4 and QuickTime store.
+pub fn parse_creation_time(s: &str) -> Option<SystemTime> {
+    let s = s.trim();
+    let (date, time) = s.split_once(['T', ' '])?;
+    let mut date_parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+
+    // Split off the offset, remembering its sign; `Z` is the same as no offset
+    let time = time.trim_end_matches(['Z', 'z']);
+    let (clock, offset_secs) = match time.rfind(['+', '-']) {
+        Some(at) => {
+            let sign = if time[at..].starts_with('-') { -1 } else { 1 };
+            let (hours, minutes) = time[at + 1..].split_once(':').unwrap_or((&time[at + 1..], "0"));
+            (&time[..at], sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60))
+        }
+        None => (time, 0),
+    };
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let seconds: f64 = clock_parts.next().unwrap_or("0").parse().ok()?;
+
+    let valid = (1..=12).contains(&month) && (1..=31).contains(&day) && hour < 24 && minute < 60 && seconds < 61.0;
+    if !valid {
+        return None;
+    }
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 - offset_secs;
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_secs_f64(seconds))
+}
+
+/// Formats a time as the UTC ISO 8601 `creation_time` ffmpeg expects.
+pub fn format_creation_time(time: SystemTime) -> String {
+    let civil = calendar_time(time);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        civil.year,
+        civil.month,
+        civil.day,
+        civil.hour,
+        civil.minute,
+        civil.second,
+        time.duration_since(UNIX_EPOCH).unwrap_or_default().subsec_micros()
+    )
+}
+
+// The UTC date and time of day of `time`, to the second
+pub(crate) fn calendar_time(time: SystemTime) -> RecordingTime {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let of_day = secs.rem_euclid(86_400);
+    RecordingTime {
+        year: year as u16,
+        month: month as u8,
+        day: day as u8,
+        hour: (of_day / 3600) as u8,
+        minute: (of_day / 60 % 60) as u8,
+        second: (of_day % 60) as u8,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date, after Howard Hinnant's `days_from_civil`
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::avchd::{clip_info_for, RecordingTime};
+use super::metadata;
+use super::probe::{probe, StreamKind};
+
+// What a token renders as when the input does not say
+const UNKNOWN: &str = "unknown";
+
+/// Where converted files go and what they are called, relative to the output directory, e.g.
+/// `{date:%Y}/{date:%m}/{stem}`. The output format's extension is added to the result.
+///
+/// - `{stem}`, `{ext}`: the input's file name without its extension, and the extension
+/// - `{date}`, `{time}`: when the clip was recorded, as `%Y-%m-%d` and `%H-%M-%S`; both take a
+///   format after a colon, such as `{date:%Y}`, made of `%Y`, `%y`, `%m`, `%d`, `%H`, `%M`, `%S` and `%%`
+/// - `{camera}`: the camera, from AVCHD clip information, as its maker and model code
+/// - `{maker}`, `{model_code}`: the camera's maker and the maker's hex code for the model on their own
+/// - `{width}`, `{height}`: the size of the video
+/// - `{index}`: the input's position in its batch, counted from 1; `{index:3}` pads it to 3 digits
+///
+/// A template that ends in `/` names a directory, and the output inside it is named after the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Stem,
+    Extension,
+    // A `strftime`-like format of the recording time
+    Recorded(String),
+    Camera,
+    Maker,
+    ModelCode,
+    Width,
+    Height,
+    // Zero-padded to this many digits
+    Index(usize),
+}
+
+impl OutputTemplate {
+    /// The output path for the `index`th input of a batch, relative to the output directory and
+    /// ending in `.extension`.
+    ///
+    /// AVCHD clip information is only read, and the input only probed, for the tokens that need it.
+    pub fn render(&self, input: &Path, index: usize, extension: &str) -> PathBuf {
+        let uses = |wanted: fn(&Part) -> bool| self.parts.iter().any(wanted);
+        let wants_clock = uses(|part| matches!(part, Part::Recorded(_)));
+        let wants_camera = uses(|part| matches!(part, Part::Camera | Part::Maker | Part::ModelCode));
+        let clip = (wants_clock || wants_camera).then(|| clip_info_for(input)).flatten();
+        let camera_clock = clip.as_ref().and_then(|clip| clip.recorded);
+        let needs_probe =
+            uses(|part| matches!(part, Part::Width | Part::Height)) || (wants_clock && camera_clock.is_none());
+        let info = needs_probe.then(|| probe(input).ok()).flatten();
+        // The camera's own clock where AVCHD records it, otherwise the creation time in UTC
+        let recorded = camera_clock.or_else(|| {
+            wants_clock.then(|| metadata::recording_time(input, info.as_ref()).map(metadata::calendar_time)).flatten()
+        });
+        let video = info.as_ref().and_then(|info| info.streams.iter().find(|stream| stream.kind == StreamKind::Video));
+        let camera = clip.as_ref().and_then(|clip| clip.camera);
+        let or_unknown = |value: Option<String>| value.unwrap_or_else(|| UNKNOWN.to_string());
+
+        let mut rendered = String::new();
+        for part in &self.parts {
+            let value = match part {
+                Part::Literal(text) => {
+                    rendered.push_str(text);
+                    continue;
+                }
+                Part::Stem => input.file_stem().unwrap_or_default().to_string_lossy().into_owned(),
+                Part::Extension => input.extension().unwrap_or_default().to_string_lossy().into_owned(),
+                Part::Recorded(format) => or_unknown(recorded.map(|time| format_time(&time, format))),
+                Part::Camera => or_unknown(camera.map(|camera| camera.to_string())),
+                Part::Maker => or_unknown(camera.map(|camera| camera.maker_name())),
+                Part::ModelCode => or_unknown(camera.map(|camera| camera.model_code_hex())),
+                Part::Width => or_unknown(video.and_then(|video| video.width).map(|width| width.to_string())),
+                Part::Height => or_unknown(video.and_then(|video| video.height).map(|height| height.to_string())),
+                Part::Index(digits) => format!("{:0digits$}", index, digits = digits),
+            };
+            rendered.push_str(&file_name_safe(&value));
+        }
+
+        let mut path: PathBuf = rendered.split('/').filter(|component| !component.is_empty()).collect();
+        let name = path.file_name().map_or_else(|| "converted".into(), |name| name.to_string_lossy().into_owned());
+        path.set_file_name(format!("{}.{}", name, extension));
+        path
+    }
+}
+
+impl Default for OutputTemplate {
+    /// Names each output after its input.
+    fn default() -> Self {
+        OutputTemplate { template: String::from("{stem}"), parts: vec![Part::Stem] }
+    }
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(String::from("the output name is empty"));
+        }
+        // Outputs must stay inside the output directory
+        let escapes = s.split(['/', '\\']).any(|component| component == "..");
+        if s.starts_with(['/', '\\']) || Path::new(s).has_root() || escapes {
+            return Err(format!("output name '{}' must be a relative path without '..'", s));
+        }
+        let expanded = if s.ends_with('/') { format!("{}{{stem}}", s) } else { s.to_string() };
+
+        let mut parts = Vec::new();
+        let mut rest = expanded.as_str();
+        while let Some(open) = rest.find(['{', '}']) {
+            if rest[open..].starts_with('}') {
+                return Err(format!("unmatched '}}' in output name '{}'", s));
+            }
+            let close = rest[open..].find('}').ok_or_else(|| format!("unclosed '{{' in output name '{}'", s))? + open;
+            if open > 0 {
+                parts.push(Part::Literal(rest[..open].to_string()));
+            }
+            parts.push(parse_token(&rest[open + 1..close])?);
+            rest = &rest[close + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(OutputTemplate { template: s.to_string(), parts })
+    }
+}
+
+fn parse_token(token: &str) -> Result<Part, String> {
+    let (name, argument) = match token.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (token, None),
+    };
+    match (name, argument) {
+        ("stem", None) => Ok(Part::Stem),
+        ("ext", None) => Ok(Part::Extension),
+        ("date", format) => check_time_format(format.unwrap_or("%Y-%m-%d")),
+        ("time", format) => check_time_format(format.unwrap_or("%H-%M-%S")),
+        ("camera", None) => Ok(Part::Camera),
+        ("maker", None) => Ok(Part::Maker),
+        ("model_code", None) => Ok(Part::ModelCode),
+        ("width", None) => Ok(Part::Width),
+        ("height", None) => Ok(Part::Height),
+        ("index", None) => Ok(Part::Index(0)),
+        ("index", Some(digits)) => digits
+            .parse()
+            .map(Part::Index)
+            .map_err(|_| format!("'{}' in {{{}}} is not a number of digits", digits, token)),
+        _ => Err(format!(
+            "unknown token {{{}}} (expected stem, ext, date, time, camera, maker, model_code, width, height or index)",
+            token
+        )),
+    }
+}
+
+fn check_time_format(format: &str) -> Result<Part, String> {
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c == '%' && !matches!(chars.next(), Some('Y' | 'y' | 'm' | 'd' | 'H' | 'M' | 'S' | '%')) {
+            return Err(format!("unsupported time format '{}' (expected %Y, %y, %m, %d, %H, %M, %S or %%)", format));
+        }
+    }
+    Ok(Part::Recorded(format.to_string()))
+}
+
+fn format_time(time: &RecordingTime, format: &str) -> String {
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => formatted.push_str(&format!("{:04}", time.year)),
+            Some('y') => formatted.push_str(&format!("{:02}", time.year % 100)),
+            Some('m') => formatted.push_str(&format!("{:02}", time.month)),
+            Some('d') => formatted.push_str(&format!("{:02}", time.day)),
+            Some('H') => formatted.push_str(&format!("{:02}", time.hour)),
+            Some('M') => formatted.push_str(&format!("{:02}", time.minute)),
+            Some('S') => formatted.push_str(&format!("{:02}", time.second)),
+            _ => formatted.push('%'),
+        }
+    }
+    formatted
+}
+
+// Values cannot add directories, and avoid the characters Windows does not allow in names
+fn file_name_safe(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
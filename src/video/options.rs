@@ -0,0 +1,136 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::cancel::CancellationToken;
+use super::encoding::{EncodingSettings, Preset};
+use super::error::ConvertError;
+use super::format::OutputFormat;
+use super::metadata::MetadataTags;
+use super::naming::OutputTemplate;
+use super::plan::StreamMode;
+use super::stderr::LogSink;
+use super::thumbnail::ThumbnailOptions;
+use super::trim::TimeRange;
+
+/// Settings shared by every conversion entry point.
+#[derive(Clone, Default)]
+pub struct ConversionOptions {
+    /// Receives each line ffmpeg writes to stderr as it happens.
+    pub stderr_sink: Option<LogSink>,
+    /// Stops the conversion, and removes its partial output, once cancelled.
+    pub cancel: CancellationToken,
+    /// What to do when the output file already exists.
+    pub overwrite: OverwritePolicy,
+    /// The output's path relative to the output directory, `{stem}` unless set.
+    pub output_name: OutputTemplate,
+    /// The container to write.
+    pub format: OutputFormat,
+    /// Whether streams are copied or re-encoded.
+    pub stream_mode: StreamMode,
+    /// Encoder settings for streams that should always be re-encoded.
+    pub encoding: EncodingSettings,
+    /// Normalizes the loudness of every audio stream to the EBU R128 target, re-encoding it.
+    pub normalize_loudness: bool,
+    /// Also extracts thumbnails from each converted video, next to it.
+    pub thumbnails: Option<ThumbnailOptions>,
+    /// Converts only this part of each input.
+    pub trim: Option<TimeRange>,
+    /// Title, comment and author to tag outputs with.
+    pub metadata: MetadataTags,
+}
+
+impl ConversionOptions {
+    /// Replaces the stream handling and encoding settings with those of `preset`.
+    pub fn apply_preset(&mut self, preset: Preset) {
+        self.stream_mode = match preset {
+            Preset::ArchiveCopy => StreamMode::Copy,
+            Preset::Share720p | Preset::WebSmall => StreamMode::Auto,
+        };
+        self.encoding = preset.encoding();
+    }
+}
+
+impl fmt::Debug for ConversionOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConversionOptions")
+            .field("stderr_sink", &self.stderr_sink.as_ref().map(|_| "Fn(&str)"))
+            .field("cancel", &self.cancel)
+            .field("overwrite", &self.overwrite)
+            .field("output_name", &self.output_name)
+            .field("format", &self.format)
+            .field("stream_mode", &self.stream_mode)
+            .field("encoding", &self.encoding)
+            .field("normalize_loudness", &self.normalize_loudness)
+            .field("thumbnails", &self.thumbnails)
+            .field("trim", &self.trim)
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+/// How to handle an output path that is already taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OverwritePolicy {
+    /// Leave the existing file alone and skip the input.
+    Skip,
+    /// Replace the existing file.
+    Overwrite,
+    /// Write to the first free `name_1.ext`, `name_2.ext`, ... next to it.
+    Rename,
+    /// Fail with [`ConvertError::OutputExists`].
+    #[default]
+    Error,
+}
+
+impl OverwritePolicy {
+    pub const ALL: [OverwritePolicy; 4] =
+        [OverwritePolicy::Skip, OverwritePolicy::Overwrite, OverwritePolicy::Rename, OverwritePolicy::Error];
+
+    /// Decides where to write `path`, or returns `None` when the input should be skipped.
+    pub fn resolve(self, path: &Path) -> Result<Option<PathBuf>, ConvertError> {
+        if !path.exists() {
+            return Ok(Some(path.to_path_buf()));
+        }
+        match self {
+            OverwritePolicy::Skip => Ok(None),
+            OverwritePolicy::Overwrite => Ok(Some(path.to_path_buf())),
+            OverwritePolicy::Rename => Ok(Some(free_path(path))),
+            OverwritePolicy::Error => Err(ConvertError::OutputExists(path.to_path_buf())),
+        }
+    }
+}
+
+fn free_path(path: &Path) -> PathBuf {
+    numbered_paths(path).find(|candidate| !candidate.exists()).unwrap()
+}
+
+// `name_1.ext`, `name_2.ext`, ... next to `path`
+pub(crate) fn numbered_paths(path: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    (1..).map(move |n| path.with_file_name(format!("{}_{}{}", stem, n, extension)))
+}
+
+impl fmt::Display for OverwritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OverwritePolicy::Skip => "skip",
+            OverwritePolicy::Overwrite => "overwrite",
+            OverwritePolicy::Rename => "rename",
+            OverwritePolicy::Error => "error",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for OverwritePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        OverwritePolicy::ALL
+            .into_iter()
+            .find(|policy| policy.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown policy '{}' (expected skip, overwrite, rename or error)", s))
+    }
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::error::ConvertError;
+use super::probe::{probe, StreamKind};
+
+/// An output that ffmpeg writes under a hidden temporary name in the target directory.
+///
+/// The file only appears under its real name once [`PendingOutput::commit`] succeeds, so a
+/// crash or cancelled run never leaves a truncated file that looks finished. Dropping an
+/// uncommitted output removes the temporary file.
+pub(crate) struct PendingOutput {
+    temp: PathBuf,
+    target: PathBuf,
+    committed: bool,
+}
+
+impl PendingOutput {
+    pub(crate) fn new(target: &Path) -> Self {
+        let file_name = target.file_name().unwrap_or_default().to_string_lossy();
+        PendingOutput {
+            temp: target.with_file_name(format!(".{}.part", file_name)),
+            target: target.to_path_buf(),
+            committed: false,
+        }
+    }
+
+    pub(crate) fn temp_path(&self) -> &Path {
+        &self.temp
+    }
+
+    /// Checks the temporary file looks like a real result and moves it into place.
+    pub(crate) fn commit(mut self) -> Result<PathBuf, ConvertError> {
+        self.check_not_empty()?;
+        self.rename_into_place()
+    }
+
+    /// Like [`PendingOutput::commit`], but first probes the temporary file, which must have a duration and at
+    /// least the `expected` streams, so a truncated or corrupt result never replaces anything.
+    ///
+    /// Without ffprobe only the size can be checked.
+    pub(crate) fn commit_media(mut self, expected: &[StreamKind]) -> Result<PathBuf, ConvertError> {
+        self.check_not_empty()?;
+        let invalid = |reason: String| ConvertError::InvalidOutput { path: self.target.clone(), reason };
+        match probe(&self.temp) {
+            Ok(info) => {
+                if info.duration.is_none_or(|duration| duration.is_zero()) {
+                    return Err(invalid(String::from("it has no duration")));
+                }
+                let kinds = [StreamKind::Video, StreamKind::Audio, StreamKind::Subtitle];
+                for (kind, name) in kinds.into_iter().zip(["video", "audio", "subtitle"]) {
+                    let wanted = expected.iter().filter(|expected| **expected == kind).count();
+                    let found = info.streams.iter().filter(|stream| stream.kind == kind).count();
+                    if found < wanted {
+                        return Err(invalid(format!("expected {} {} stream(s), found {}", wanted, name, found)));
+                    }
+                }
+            }
+            Err(ConvertError::FfprobeNotFound) => {}
+            Err(e) => return Err(invalid(e.to_string())),
+        }
+        self.rename_into_place()
+    }
+
+    fn check_not_empty(&self) -> Result<(), ConvertError> {
+        let metadata = fs::metadata(&self.temp).map_err(|e| ConvertError::io(&self.temp, e))?;
+        if metadata.len() == 0 {
+            return Err(ConvertError::EmptyOutput(self.target.clone()));
+        }
+        Ok(())
+    }
+
+    fn rename_into_place(&mut self) -> Result<PathBuf, ConvertError> {
+        // Renaming within one directory is atomic and replaces an existing target
+        fs::rename(&self.temp, &self.target).map_err(|e| ConvertError::io(&self.target, e))?;
+        self.committed = true;
+        Ok(self.target.clone())
+    }
+}
+
+impl Drop for PendingOutput {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.temp);
+        }
+    }
+}
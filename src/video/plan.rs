@@ -0,0 +1,198 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::encoding::EncodingSettings;
+use super::format::OutputFormat;
+use super::probe::{MediaInfo, StreamInfo, StreamKind};
+
+// Text subtitles that can be converted to any container's own subtitle format
+const TEXT_SUBTITLE_CODECS: [&str; 6] = ["subrip", "ass", "ssa", "webvtt", "text", "mov_text"];
+
+/// How the converter decides between copying and re-encoding streams.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StreamMode {
+    /// Copy streams the output format's players accept and re-encode the rest.
+    #[default]
+    Auto,
+    /// Copy every stream the output format can hold, even if players may reject it.
+    Copy,
+    /// Re-encode every video and audio stream.
+    Transcode,
+}
+
+impl StreamMode {
+    pub const ALL: [StreamMode; 3] = [StreamMode::Auto, StreamMode::Copy, StreamMode::Transcode];
+}
+
+impl fmt::Display for StreamMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StreamMode::Auto => "auto",
+            StreamMode::Copy => "copy",
+            StreamMode::Transcode => "transcode",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for StreamMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StreamMode::ALL
+            .into_iter()
+            .find(|mode| mode.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown stream mode '{}' (expected auto, copy or transcode)", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamAction {
+    Copy,
+    /// Re-encode with the named ffmpeg encoder.
+    Transcode { encoder: String },
+    /// Leave the stream out because the output format cannot hold it.
+    Drop,
+}
+
+/// The decision for one input stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamPlan {
+    pub index: usize,
+    pub kind: StreamKind,
+    pub codec: Option<String>,
+    pub action: StreamAction,
+}
+
+impl StreamPlan {
+    /// The bitstream filter a copied stream needs to be stored in the output.
+    ///
+    /// Transport streams frame AAC as ADTS, which MP4, QuickTime and Matroska store as raw AAC with
+    /// its configuration in the header. H.264 needs nothing: those muxers convert Annex B themselves.
+    pub fn bitstream_filter(&self) -> Option<&'static str> {
+        match (&self.action, self.codec.as_deref()) {
+            (StreamAction::Copy, Some("aac")) => Some("aac_adtstoasc"),
+            _ => None,
+        }
+    }
+}
+
+/// Which input streams end up in the output, and how.
+///
+/// The plan is empty when the input could not be probed; ffmpeg then copies the
+/// streams it selects by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversionPlan {
+    pub streams: Vec<StreamPlan>,
+}
+
+impl ConversionPlan {
+    /// Plans an MP4 conversion of the probed streams.
+    pub fn for_mp4(info: &MediaInfo, mode: StreamMode) -> Self {
+        ConversionPlan::for_format(info, OutputFormat::Mp4, mode)
+    }
+
+    /// Plans a conversion of the probed streams to `format`.
+    pub fn for_format(info: &MediaInfo, format: OutputFormat, mode: StreamMode) -> Self {
+        let streams = info
+            .streams
+            .iter()
+            .map(|stream| StreamPlan {
+                index: stream.index,
+                kind: stream.kind,
+                codec: stream.codec.clone(),
+                action: stream_action(stream, format, mode),
+            })
+            .collect();
+        let mut plan = ConversionPlan { streams };
+        if format.holds_single_stream() {
+            plan.streams
+                .iter_mut()
+                .filter(|stream| stream.action != StreamAction::Drop)
+                .skip(1)
+                .for_each(|stream| stream.action = StreamAction::Drop);
+        }
+        plan
+    }
+
+    /// Re-encodes copied audio streams with the format's usual encoder, since filters such as
+    /// loudness normalization cannot be applied to a copy.
+    pub fn with_audio_reencoded(mut self, format: OutputFormat) -> Self {
+        let encoder = format.default_encoder(StreamKind::Audio).unwrap_or("aac");
+        for stream in &mut self.streams {
+            if stream.kind == StreamKind::Audio && stream.action == StreamAction::Copy {
+                stream.action = StreamAction::Transcode { encoder: encoder.to_string() };
+            }
+        }
+        self
+    }
+
+    /// Re-encodes the video and audio streams `encoding` has settings for. Dropped streams stay dropped.
+    pub fn with_encoding(mut self, encoding: &EncodingSettings) -> Self {
+        for stream in self.streams.iter_mut().filter(|stream| stream.action != StreamAction::Drop) {
+            let encoder = match stream.kind {
+                StreamKind::Video => encoding.video.as_ref().map(|video| video.codec.encoder()),
+                StreamKind::Audio => encoding.audio.as_ref().map(|audio| audio.codec.encoder()),
+                _ => None,
+            };
+            if let Some(encoder) = encoder {
+                stream.action = StreamAction::Transcode { encoder: encoder.to_string() };
+            }
+        }
+        self
+    }
+
+    /// Whether every stream that is kept is copied as it is.
+    pub fn is_remux(&self) -> bool {
+        self.streams.iter().all(|stream| !matches!(stream.action, StreamAction::Transcode { .. }))
+    }
+
+    /// Whether a video stream is copied, so cuts can only fall on its keyframes.
+    pub fn copies_video(&self) -> bool {
+        self.kept().any(|stream| stream.kind == StreamKind::Video && stream.action == StreamAction::Copy)
+    }
+
+    /// The streams that end up in the output, in output order.
+    pub fn kept(&self) -> impl Iterator<Item = &StreamPlan> {
+        self.streams.iter().filter(|stream| stream.action != StreamAction::Drop)
+    }
+}
+
+fn stream_action(stream: &StreamInfo, format: OutputFormat, mode: StreamMode) -> StreamAction {
+    let codec = stream.codec.as_deref().unwrap_or_default();
+    let transcode = || match format.default_encoder(stream.kind) {
+        Some(encoder) => StreamAction::Transcode { encoder: encoder.to_string() },
+        None => StreamAction::Drop,
+    };
+    match stream.kind {
+        StreamKind::Video | StreamKind::Audio => match mode {
+            StreamMode::Copy if format.can_hold(stream.kind, codec) => StreamAction::Copy,
+            StreamMode::Auto if format.plays(stream.kind, codec) => StreamAction::Copy,
+            _ => transcode(),
+        },
+        StreamKind::Subtitle if format.can_hold(stream.kind, codec) => StreamAction::Copy,
+        StreamKind::Subtitle if TEXT_SUBTITLE_CODECS.contains(&codec) => transcode(),
+        // Bitmap subtitles the container cannot hold, and data streams, are left out
+        StreamKind::Subtitle | StreamKind::Data | StreamKind::Other => StreamAction::Drop,
+    }
+}
+
+impl fmt::Display for ConversionPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.streams.is_empty() {
+            return f.write_str("copy default streams");
+        }
+        for (i, stream) in self.streams.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            let codec = stream.codec.as_deref().unwrap_or("unknown");
+            match stream.action {
+                StreamAction::Copy => write!(f, "#{} {} copy", stream.index, codec)?,
+                StreamAction::Transcode { ref encoder } => write!(f, "#{} {} -> {}", stream.index, codec, encoder)?,
+                StreamAction::Drop => write!(f, "#{} {} dropped", stream.index, codec)?,
+            }
+        }
+        Ok(())
+    }
+}
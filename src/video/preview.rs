@@ -0,0 +1,149 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::error::ConvertError;
+use super::options::ConversionOptions;
+use super::output::PendingOutput;
+use super::probe::probe;
+use super::progress::{ConversionPhase, ProgressEventCallback, ProgressReporter, ProgressTotals, ProgressTracker};
+use super::thumbnail::ThumbnailTime;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    Webp,
+}
+
+impl AnimationFormat {
+    pub const ALL: [AnimationFormat; 2] = [AnimationFormat::Gif, AnimationFormat::Webp];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Webp => "webp",
+        }
+    }
+}
+
+impl fmt::Display for AnimationFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+impl FromStr for AnimationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AnimationFormat::ALL
+            .into_iter()
+            .find(|format| format.extension().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown animation format '{}' (expected gif or webp)", s))
+    }
+}
+
+/// The part of a clip a preview shows, and how it is scaled down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewOptions {
+    pub format: AnimationFormat,
+    pub start: ThumbnailTime,
+    /// Cut short if the clip ends first.
+    pub duration: Duration,
+    pub width: u32,
+    pub fps: f64,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        PreviewOptions {
+            format: AnimationFormat::default(),
+            start: ThumbnailTime::default(),
+            duration: Duration::from_secs(3),
+            width: 480,
+            fps: 12.0,
+        }
+    }
+}
+
+/// Writes a looping animated preview of part of `input` as `{stem}_preview.{ext}`.
+///
+/// Uses the overwrite policy, cancellation and stderr sink of `options`. Returns `None` when the
+/// preview already exists and [`OverwritePolicy::Skip`](super::OverwritePolicy::Skip) left it alone.
+pub fn create_preview(
+    input_path: &Path,
+    output_dir: Option<&Path>,
+    preview: &PreviewOptions,
+    options: &ConversionOptions,
+    event_callback: Option<ProgressEventCallback>,
+) -> Result<Option<PathBuf>, ConvertError> {
+    if !input_path.is_file() {
+        return Err(ConvertError::InputNotFound(input_path.to_path_buf()));
+    }
+    let output_dir = output_dir.unwrap_or_else(|| input_path.parent().unwrap());
+    let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
+    let output_path = output_dir.join(format!("{}_preview.{}", stem, preview.format.extension()));
+
+    let reporter = ProgressReporter::new(input_path.to_path_buf(), event_callback);
+    let Some(output_path) = options.overwrite.resolve(&output_path)? else {
+        let mut tracker = ProgressTracker::new(ProgressTotals::default());
+        tracker.finish();
+        reporter.report(ConversionPhase::Finished, &tracker);
+        println!("Skipped {}: {} already exists", input_path.display(), output_path.display());
+        return Ok(None);
+    };
+    reporter.report(ConversionPhase::Probing, &ProgressTracker::new(ProgressTotals::default()));
+    let clip_duration = probe(input_path).ok().and_then(|info| info.duration);
+    let start = preview.start.resolve(clip_duration);
+    let duration = match clip_duration {
+        Some(clip) => preview.duration.min(clip.saturating_sub(start)),
+        None => preview.duration,
+    };
+    let frames = (duration.as_secs_f64() * preview.fps).round() as u64;
+    let tracker = ProgressTracker::new(ProgressTotals { duration: Some(duration), frames: Some(frames), bytes: None });
+    if options.cancel.is_cancelled() {
+        return Err(ConvertError::Cancelled);
+    }
+    reporter.report(ConversionPhase::Converting, &tracker);
+
+    let pending = PendingOutput::new(&output_path);
+    let mut command = super::ffmpeg_command();
+    command
+        .args(["-y", "-ss"])
+        .arg(format!("{:.3}", start.as_secs_f64()))
+        .arg("-t")
+        .arg(format!("{:.3}", duration.as_secs_f64()))
+        .arg("-i")
+        .arg(input_path);
+    let frames_filter = format!("fps={},scale={}:-1:flags=lanczos", preview.fps, preview.width);
+    match preview.format {
+        // GIF has 256 colours per frame; a palette built from the clip itself avoids the washed-out default
+        AnimationFormat::Gif => {
+            let graph = format!(
+                "[0:v:0]{},split[frames][sample];[sample]palettegen=stats_mode=diff[palette];\
+                 [frames][palette]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+                frames_filter
+            );
+            command.arg("-filter_complex").arg(graph).args(["-f", "gif"]);
+        }
+        AnimationFormat::Webp => {
+            command
+                .args(["-map", "0:v:0", "-filter:v"])
+                .arg(frames_filter)
+                .args(["-c:v", "libwebp", "-quality", "75", "-f", "webp"]);
+        }
+    }
+    command
+        .args(["-an", "-loop", "0", "-progress", "-", "-nostats"])
+        .arg(pending.temp_path());
+    let mut tracker = super::run_ffmpeg(command, input_path, &output_path, options, tracker, &reporter)?;
+
+    reporter.report(ConversionPhase::Finalizing, &tracker);
+    let output_path = pending.commit()?;
+    tracker.finish();
+    reporter.report(ConversionPhase::Finished, &tracker);
+    println!("Created preview {}", output_path.display());
+    Ok(Some(output_path))
+}
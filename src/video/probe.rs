@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+use super::error::ConvertError;
+use super::progress::{parse_rational, ProgressTotals};
+
+/// What ffprobe reports about a media file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MediaInfo {
+    pub path: PathBuf,
+    /// ffprobe's short format name, e.g. `mpegts` or `mov,mp4,m4a,3gp,3g2,mj2`.
+    pub container: String,
+    pub container_long_name: Option<String>,
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Option<Duration>,
+    /// The timestamp of the first frame; AVCHD segments of one recording carry on from each other's.
+    #[serde(serialize_with = "serialize_secs")]
+    pub start_time: Option<Duration>,
+    /// Overall bitrate in bits per second.
+    pub bit_rate: Option<u64>,
+    pub size: Option<u64>,
+    /// The `creation_time` tag as recorded by the camera, usually ISO 8601.
+    pub creation_time: Option<String>,
+    pub streams: Vec<StreamInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Other,
+}
+
+/// One stream of a [`MediaInfo`]; fields that do not apply to the stream's kind are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamInfo {
+    pub index: usize,
+    pub kind: StreamKind,
+    pub codec: Option<String>,
+    pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub frames: Option<u64>,
+    pub pixel_format: Option<String>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub bit_rate: Option<u64>,
+    pub language: Option<String>,
+}
+
+impl MediaInfo {
+    /// Parses the output of `ffprobe -of json -show_format -show_streams`.
+    pub fn from_ffprobe_json(path: &Path, json: &str) -> Result<Self, serde_json::Error> {
+        let raw: RawProbe = serde_json::from_str(json)?;
+        let format = raw.format.unwrap_or_default();
+        let mut streams: Vec<StreamInfo> = raw.streams.into_iter().map(StreamInfo::from).collect();
+        streams.sort_by_key(|stream| stream.index);
+
+        Ok(MediaInfo {
+            path: path.to_path_buf(),
+            container: format.format_name.unwrap_or_default(),
+            container_long_name: format.format_long_name,
+            duration: format
+                .duration
+                .and_then(|secs| secs.parse::<f64>().ok())
+                .filter(|secs| secs.is_finite() && *secs > 0.0)
+                .map(Duration::from_secs_f64),
+            start_time: format
+                .start_time
+                .and_then(|secs| secs.parse::<f64>().ok())
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .map(Duration::from_secs_f64),
+            bit_rate: format.bit_rate.and_then(|rate| rate.parse().ok()),
+            size: format.size.and_then(|size| size.parse().ok()),
+            creation_time: format.tags.get("creation_time").cloned(),
+            streams,
+        })
+    }
+
+    /// The first video stream, which is the one players show.
+    pub fn video_stream(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|stream| stream.kind == StreamKind::Video)
+    }
+
+    /// Whether this looks like a video recording rather than a still image or audio file.
+    ///
+    /// Images also have a video stream, but no duration.
+    pub fn is_recording(&self) -> bool {
+        let image = self.container.starts_with("image2") || self.container.ends_with("_pipe");
+        self.video_stream().is_some() && self.duration.is_some() && !image
+    }
+
+    pub fn audio_streams(&self) -> impl Iterator<Item = &StreamInfo> {
+        self.streams.iter().filter(|stream| stream.kind == StreamKind::Audio)
+    }
+
+    /// The duration and frame count to measure conversion progress against.
+    pub fn progress_totals(&self) -> ProgressTotals {
+        let video = self.video_stream();
+        let frames = video.and_then(|stream| stream.frames).or_else(|| {
+            let rate = video?.frame_rate?;
+            Some((self.duration?.as_secs_f64() * rate).round() as u64).filter(|n| *n > 0)
+        });
+        ProgressTotals { duration: self.duration, frames, bytes: self.size }
+    }
+}
+
+impl From<RawStream> for StreamInfo {
+    fn from(raw: RawStream) -> Self {
+        let kind = match raw.codec_type.as_deref() {
+            Some("video") => StreamKind::Video,
+            Some("audio") => StreamKind::Audio,
+            Some("subtitle") => StreamKind::Subtitle,
+            Some("data") => StreamKind::Data,
+            _ => StreamKind::Other,
+        };
+        // Interlaced AVCHD often reports `0/0` as its average rate
+        let frame_rate = [&raw.avg_frame_rate, &raw.r_frame_rate]
+            .into_iter()
+            .find_map(|rate| rate.as_deref().and_then(parse_rational))
+            .filter(|_| kind == StreamKind::Video);
+
+        StreamInfo {
+            index: raw.index,
+            kind,
+            codec: raw.codec_name,
+            codec_long_name: raw.codec_long_name,
+            profile: raw.profile,
+            width: raw.width.filter(|width| *width > 0),
+            height: raw.height.filter(|height| *height > 0),
+            frame_rate,
+            frames: raw.nb_frames.and_then(|frames| frames.parse().ok()).filter(|frames| *frames > 0),
+            pixel_format: raw.pix_fmt,
+            channels: raw.channels.filter(|channels| *channels > 0),
+            channel_layout: raw.channel_layout,
+            sample_rate: raw.sample_rate.and_then(|rate| rate.parse().ok()),
+            bit_rate: raw.bit_rate.and_then(|rate| rate.parse().ok()),
+            language: raw.tags.get("language").filter(|lang| lang.as_str() != "und").cloned(),
+        }
+    }
+}
+
+// ffprobe's JSON writer quotes most numbers, so they are parsed after deserializing
+#[derive(Debug, Default, Deserialize)]
+struct RawProbe {
+    #[serde(default)]
+    streams: Vec<RawStream>,
+    format: Option<RawFormat>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawFormat {
+    format_name: Option<String>,
+    format_long_name: Option<String>,
+    duration: Option<String>,
+    start_time: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStream {
+    index: usize,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    codec_long_name: Option<String>,
+    profile: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    nb_frames: Option<String>,
+    pix_fmt: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    sample_rate: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+fn serialize_secs<S: Serializer>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error> {
+    duration.map(|duration| duration.as_secs_f64()).serialize(serializer)
+}
+
+/// Inspects `path` with ffprobe.
+pub fn probe(path: &Path) -> Result<MediaInfo, ConvertError> {
+    if !path.is_file() {
+        return Err(ConvertError::InputNotFound(path.to_path_buf()));
+    }
+    let output = super::ffprobe_command()
+        .args(["-v", "error", "-of", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => ConvertError::FfprobeNotFound,
+            _ => ConvertError::io(path, e),
+        })?;
+
+    if !output.status.success() {
+        let stderr_tail = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(ConvertError::ProbeFailed { path: path.to_path_buf(), stderr_tail });
+    }
+    MediaInfo::from_ffprobe_json(path, &String::from_utf8_lossy(&output.stdout))
+        .map_err(|e| ConvertError::io(path, io::Error::new(io::ErrorKind::InvalidData, e)))
+}
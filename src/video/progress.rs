@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// What is known about the input up front, used as the denominator for progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProgressTotals {
+    pub duration: Option<Duration>,
+    pub frames: Option<u64>,
+    pub bytes: Option<u64>,
+}
+
+pub(crate) fn parse_rational(value: &str) -> Option<f64> {
+    let (num, den) = value.split_once('/').unwrap_or((value, "1"));
+    let num = num.parse::<f64>().ok()?;
+    let den = den.parse::<f64>().ok()?;
+    let rate = num / den;
+    (rate.is_finite() && rate > 0.0).then_some(rate)
+}
+
+/// Turns ffmpeg's `-progress` key/value stream into a fraction that only ever
+/// moves forward and always stays within `0.0..=1.0`.
+///
+/// The elapsed output time is used when the input duration is known, then the
+/// frame count, and finally the number of bytes written against the input size,
+/// which is a close estimate for stream copies.
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    totals: ProgressTotals,
+    current: f32,
+    stats: ProgressStats,
+}
+
+/// The latest statistics ffmpeg reported; fields stay `None` while ffmpeg reports `N/A`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressStats {
+    pub frame: Option<u64>,
+    pub fps: Option<f32>,
+    pub bitrate_kbps: Option<f64>,
+    pub out_time: Option<Duration>,
+    pub speed: Option<f32>,
+    pub bytes_written: Option<u64>,
+}
+
+impl ProgressStats {
+    fn record(&mut self, key: &str, value: &str) {
+        match key {
+            "frame" => self.frame = value.parse().ok(),
+            "fps" => self.fps = value.parse().ok(),
+            "bitrate" => self.bitrate_kbps = value.trim_end_matches("kbits/s").parse().ok(),
+            "out_time_us" => {
+                self.out_time = value.parse::<u64>().ok().map(Duration::from_micros);
+            }
+            "speed" => self.speed = value.trim_end_matches('x').trim().parse().ok(),
+            "total_size" => self.bytes_written = value.parse().ok(),
+            _ => {}
+        }
+    }
+}
+
+impl ProgressTracker {
+    pub fn new(totals: ProgressTotals) -> Self {
+        ProgressTracker { totals, current: 0.0, stats: ProgressStats::default() }
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn stats(&self) -> &ProgressStats {
+        &self.stats
+    }
+
+    /// Feeds one `key=value` pair and returns the new progress if it advanced.
+    pub fn update(&mut self, key: &str, value: &str) -> Option<f32> {
+        let value = value.trim();
+        self.stats.record(key, value);
+        let fraction = match key {
+            "out_time_us" | "out_time_ms" => {
+                let total = self.totals.duration?.as_micros() as f64;
+                value.parse::<i64>().ok()? as f64 / total
+            }
+            "frame" if self.totals.duration.is_none() => {
+                let total = self.totals.frames? as f64;
+                value.parse::<u64>().ok()? as f64 / total
+            }
+            "total_size" if self.totals.duration.is_none() && self.totals.frames.is_none() => {
+                let total = self.totals.bytes? as f64;
+                value.parse::<u64>().ok()? as f64 / total
+            }
+            _ => return None,
+        };
+        self.advance(fraction as f32)
+    }
+
+    /// Marks the conversion as complete, returning `Some(1.0)` unless already reported.
+    pub fn finish(&mut self) -> Option<f32> {
+        self.advance(1.0)
+    }
+
+    fn advance(&mut self, fraction: f32) -> Option<f32> {
+        if !fraction.is_finite() {
+            return None;
+        }
+        let fraction = fraction.clamp(0.0, 1.0);
+        if fraction > self.current {
+            self.current = fraction;
+            Some(fraction)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionPhase {
+    Probing,
+    Converting,
+    Finalizing,
+    Finished,
+}
+
+/// A snapshot of a running conversion, delivered to a [`ProgressEventCallback`].
+///
+/// `progress` covers the current file while `overall_progress` covers the whole
+/// job; both stay within `0.0..=1.0` and never move backwards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    pub file: PathBuf,
+    pub file_index: usize,
+    pub file_count: usize,
+    pub phase: ConversionPhase,
+    pub progress: f32,
+    pub overall_progress: f32,
+    pub frame: Option<u64>,
+    pub fps: Option<f32>,
+    pub bitrate_kbps: Option<f64>,
+    pub out_time: Option<Duration>,
+    pub speed: Option<f32>,
+    pub eta: Option<Duration>,
+    pub bytes_written: Option<u64>,
+}
+
+pub type ProgressEventCallback = Arc<dyn Fn(&ProgressEvent) + Send + Sync + 'static>;
+
+/// Builds [`ProgressEvent`]s for a single file and hands them to the callback.
+#[derive(Clone)]
+pub(crate) struct ProgressReporter {
+    callback: Option<ProgressEventCallback>,
+    file: PathBuf,
+    started: Instant,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(file: PathBuf, callback: Option<ProgressEventCallback>) -> Self {
+        ProgressReporter { callback, file, started: Instant::now() }
+    }
+
+    pub(crate) fn report(&self, phase: ConversionPhase, tracker: &ProgressTracker) {
+        let Some(ref callback) = self.callback else {
+            return;
+        };
+        let progress = tracker.current();
+        let stats = tracker.stats().clone();
+        let eta = match phase {
+            ConversionPhase::Finished => Some(Duration::ZERO),
+            _ if progress > 0.0 => {
+                let elapsed = self.started.elapsed().as_secs_f64();
+                Some(Duration::from_secs_f64(elapsed * f64::from(1.0 - progress) / f64::from(progress)))
+            }
+            _ => None,
+        };
+
+        callback(&ProgressEvent {
+            file: self.file.clone(),
+            file_index: 0,
+            file_count: 1,
+            phase,
+            progress,
+            overall_progress: progress,
+            frame: stats.frame,
+            fps: stats.fps,
+            bitrate_kbps: stats.bitrate_kbps,
+            out_time: stats.out_time,
+            speed: stats.speed,
+            eta,
+            bytes_written: stats.bytes_written,
+        });
+    }
+}
+
+/// Formats a duration as `HH:MM:SS` for progress displays.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
@@ -0,0 +1,133 @@
+use std::fs;
+use std::io::Write;
+use std::path::{self, Path, PathBuf};
+use std::time::Duration;
+
+use super::avchd::SegmentLinks;
+use super::batch::BatchInput;
+use super::error::ConvertError;
+use super::probe::{probe, MediaInfo, StreamInfo};
+
+// How far apart the end of one segment and the start of the next may be and still count as one recording
+const SEGMENT_GAP_TOLERANCE: Duration = Duration::from_millis(500);
+
+/// Whether `next` carries on the recording `earlier` was cut from.
+///
+/// AVCHD cameras split long recordings at the FAT32 size limit without resetting the clock, so the
+/// next segment starts where the last one ended, with the same streams.
+pub fn is_continuation(earlier: &MediaInfo, next: &MediaInfo) -> bool {
+    let (Some(start), Some(duration), Some(next_start)) = (earlier.start_time, earlier.duration, next.start_time) else {
+        return false;
+    };
+    let gap = next_start.abs_diff(start + duration);
+    let layout = |stream: &StreamInfo| (stream.kind, stream.codec.clone(), stream.width, stream.height);
+    gap <= SEGMENT_GAP_TOLERANCE && earlier.streams.iter().map(layout).eq(next.streams.iter().map(layout))
+}
+
+// Camera numbering such as `00000.MTS` followed by `00001.MTS` in the same directory
+fn numbered_in_sequence(earlier: &Path, next: &Path) -> bool {
+    let number = |path: &Path| {
+        let stem = path.file_stem()?.to_str()?;
+        if !stem.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some((stem.parse::<u64>().ok()?, stem.len()))
+    };
+    let extension = |path: &Path| path.extension().map(|ext| ext.to_ascii_lowercase());
+    match (number(earlier), number(next)) {
+        (Some((earlier_number, width)), Some((next_number, next_width))) => {
+            width == next_width
+                && next_number == earlier_number + 1
+                && earlier.parent() == next.parent()
+                && extension(earlier) == extension(next)
+        }
+        _ => false,
+    }
+}
+
+/// Folds each input that continues the previous one into that input's `segments`.
+///
+/// Only files numbered in sequence are considered. The card's AVCHD playlists decide where they
+/// list both files; otherwise the files are probed, so unrelated recordings cost nothing.
+pub(crate) fn join_segments(inputs: Vec<BatchInput>) -> Vec<BatchInput> {
+    let mut joined: Vec<BatchInput> = Vec::new();
+    let mut playlists = SegmentLinks::default();
+    // The probe of the last segment of the last input, when it was needed
+    let mut previous: Option<MediaInfo> = None;
+    for input in inputs {
+        let mut info = None;
+        if let Some(recording) = joined.last_mut() {
+            if numbered_in_sequence(recording.last_segment(), &input.path) {
+                let continues = match playlists.continues(recording.last_segment(), &input.path) {
+                    Some(continues) => continues,
+                    None => {
+                        let earlier = previous.take().or_else(|| probe(recording.last_segment()).ok());
+                        info = probe(&input.path).ok();
+                        earlier.as_ref().zip(info.as_ref()).is_some_and(|(earlier, info)| is_continuation(earlier, info))
+                    }
+                };
+                if continues {
+                    recording.segments.push(input.path);
+                    previous = info;
+                    continue;
+                }
+            }
+        }
+        previous = info;
+        joined.push(input);
+    }
+    for recording in joined.iter().filter(|recording| !recording.segments.is_empty()) {
+        println!(
+            "Joining {} with {} following segment(s) of the same recording",
+            recording.path.display(),
+            recording.segments.len()
+        );
+    }
+    joined
+}
+
+/// Probes every segment and describes them as one recording: the first segment's streams, with the
+/// durations, sizes and frame counts added up. `None` if any segment cannot be probed.
+pub(crate) fn probe_joined(segments: &[PathBuf]) -> Option<MediaInfo> {
+    let mut infos = segments.iter().map(|segment| probe(segment).ok());
+    let mut joined = infos.next()??;
+    for info in infos {
+        let info = info?;
+        joined.duration = joined.duration.zip(info.duration).map(|(total, duration)| total + duration);
+        joined.size = joined.size.zip(info.size).map(|(total, size)| total + size);
+        for (stream, next) in joined.streams.iter_mut().zip(&info.streams) {
+            stream.frames = stream.frames.zip(next.frames).map(|(total, frames)| total + frames);
+        }
+    }
+    Some(joined)
+}
+
+/// An input list for ffmpeg's concat demuxer, written next to the output and removed when dropped.
+pub(crate) struct ConcatList {
+    path: PathBuf,
+}
+
+impl ConcatList {
+    pub(crate) fn write(output: &Path, segments: &[PathBuf]) -> Result<Self, ConvertError> {
+        let file_name = output.file_name().unwrap_or_default().to_string_lossy();
+        let list = ConcatList { path: output.with_file_name(format!(".{}.concat", file_name)) };
+        let mut file = fs::File::create(&list.path).map_err(|e| ConvertError::io(&list.path, e))?;
+        for segment in segments {
+            // The demuxer resolves relative paths against the list, so every entry is made absolute
+            let segment = path::absolute(segment).map_err(|e| ConvertError::io(segment, e))?;
+            let quoted = segment.to_string_lossy().replace('\'', r"'\''");
+            writeln!(file, "file '{}'", quoted).map_err(|e| ConvertError::io(&list.path, e))?;
+        }
+        Ok(list)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ConcatList {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
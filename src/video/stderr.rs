@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+pub type LogSink = Arc<dyn Fn(&str) + Send + Sync + 'static>;
+
+// Longer lines are truncated so a runaway line cannot grow the buffer without bound
+const MAX_LINE_BYTES: usize = 4096;
+
+/// Drains a child's stderr on a background thread, keeping only the last `capacity` lines.
+///
+/// ffmpeg can write megabytes of diagnostics on long or damaged inputs; if nobody reads
+/// the pipe it fills up and ffmpeg blocks forever.
+pub(crate) struct StderrCollector {
+    handle: JoinHandle<VecDeque<String>>,
+}
+
+impl StderrCollector {
+    pub(crate) fn spawn<R: Read + Send + 'static>(pipe: R, capacity: usize, sink: Option<LogSink>) -> Self {
+        let handle = thread::spawn(move || {
+            let mut reader = BufReader::new(pipe);
+            let mut tail = VecDeque::with_capacity(capacity);
+            let mut line = Vec::new();
+            let mut push_line = |line: &mut Vec<u8>| {
+                if line.is_empty() {
+                    return;
+                }
+                let text = String::from_utf8_lossy(line).into_owned();
+                line.clear();
+                if let Some(ref sink) = sink {
+                    sink(&text);
+                }
+                if tail.len() == capacity {
+                    tail.pop_front();
+                }
+                if capacity > 0 {
+                    tail.push_back(text);
+                }
+            };
+
+            loop {
+                let buf = match reader.fill_buf() {
+                    Ok([]) => break,
+                    Ok(buf) => buf,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                };
+                for &byte in buf {
+                    // ffmpeg terminates its status lines with '\r'
+                    if byte == b'\n' || byte == b'\r' {
+                        push_line(&mut line);
+                    } else if line.len() < MAX_LINE_BYTES {
+                        line.push(byte);
+                    }
+                }
+                let consumed = buf.len();
+                reader.consume(consumed);
+            }
+            push_line(&mut line);
+            tail
+        });
+        StderrCollector { handle }
+    }
+
+    /// Waits for the pipe to close and returns the retained lines.
+    pub(crate) fn finish(self) -> String {
+        self.handle
+            .join()
+            .map(|tail| Vec::from(tail).join("\n"))
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::batch::{collect_inputs, BatchOptions};
+use super::error::ConvertError;
+use super::options::OverwritePolicy;
+use super::output::PendingOutput;
+use super::probe::probe;
+use super::trim::parse_timestamp;
+
+// Frames of a contact sheet are scaled to this width unless another is asked for
+const DEFAULT_TILE_WIDTH: u32 = 320;
+// Without a known duration, a contact sheet takes a frame every this many seconds
+const FALLBACK_SHEET_INTERVAL_SECS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl ImageFormat {
+    pub const ALL: [ImageFormat; 3] = [ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::Webp];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::Webp => "webp",
+        }
+    }
+
+    // Encoder and quality; the image2 muxer is named because the temporary file's extension is `.part`
+    fn encoder_args(self) -> &'static [&'static str] {
+        match self {
+            ImageFormat::Jpeg => &["-c:v", "mjpeg", "-q:v", "3"],
+            ImageFormat::Png => &["-c:v", "png"],
+            ImageFormat::Webp => &["-c:v", "libwebp", "-quality", "80"],
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Png => "png",
+            ImageFormat::Webp => "webp",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ImageFormat::ALL
+            .into_iter()
+            .find(|format| format.to_string().eq_ignore_ascii_case(s) || format.extension().eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("unknown image format '{}' (expected jpeg, png or webp)", s))
+    }
+}
+
+/// Where in a clip the poster frame is taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbnailTime {
+    /// A fixed time from the start, moved back into the clip if the clip is shorter.
+    At(Duration),
+    /// A percentage of the clip's duration.
+    Percent(f64),
+}
+
+impl Default for ThumbnailTime {
+    // Far enough in to skip the fade-in many cameras start with
+    fn default() -> Self {
+        ThumbnailTime::Percent(10.0)
+    }
+}
+
+impl ThumbnailTime {
+    pub(crate) fn resolve(self, duration: Option<Duration>) -> Duration {
+        match (self, duration) {
+            (ThumbnailTime::At(at), Some(duration)) if at >= duration => duration.mul_f64(0.9),
+            (ThumbnailTime::At(at), _) => at,
+            (ThumbnailTime::Percent(percent), Some(duration)) => duration.mul_f64(percent / 100.0),
+            (ThumbnailTime::Percent(_), None) => Duration::ZERO,
+        }
+    }
+}
+
+/// Parses `25%`, seconds such as `12.5`, or `[hh:]mm:ss[.frac]`.
+impl FromStr for ThumbnailTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time '{}' (expected e.g. 25%, 12.5 or 01:30)", s);
+        if let Some(percent) = s.trim().strip_suffix('%') {
+            let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err(format!("percentage '{}' is not between 0 and 100", s));
+            }
+            return Ok(ThumbnailTime::Percent(percent));
+        }
+        parse_timestamp(s).map(ThumbnailTime::At).map_err(|_| invalid())
+    }
+}
+
+/// What to extract from each clip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThumbnailOptions {
+    pub format: ImageFormat,
+    pub time: ThumbnailTime,
+    /// Scales the poster frame to this width; contact sheet frames default to 320 pixels wide.
+    pub width: Option<u32>,
+    /// Also writes a grid of this many evenly spaced frames as `{stem}_sheet.{ext}`.
+    pub contact_sheet: Option<u32>,
+    pub overwrite: OverwritePolicy,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions {
+            format: ImageFormat::default(),
+            time: ThumbnailTime::default(),
+            width: None,
+            contact_sheet: None,
+            overwrite: OverwritePolicy::Error,
+        }
+    }
+}
+
+/// The images written for one clip; `None` where nothing was written because the file existed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Thumbnails {
+    pub poster: Option<PathBuf>,
+    pub contact_sheet: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ThumbnailEntry {
+    pub input: PathBuf,
+    pub result: Result<Thumbnails, ConvertError>,
+}
+
+/// Writes the poster frame, and the contact sheet if asked for, of `input` as `{stem}.{ext}`.
+pub fn create_thumbnails(
+    input: &Path,
+    output_dir: Option<&Path>,
+    options: &ThumbnailOptions,
+) -> Result<Thumbnails, ConvertError> {
+    if !input.is_file() {
+        return Err(ConvertError::InputNotFound(input.to_path_buf()));
+    }
+    let output_dir = output_dir.unwrap_or_else(|| input.parent().unwrap());
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = options.format.extension();
+    let duration = probe(input).ok().and_then(|info| info.duration);
+
+    let mut thumbnails = Thumbnails::default();
+    if let Some(poster) = options.overwrite.resolve(&output_dir.join(format!("{}.{}", stem, extension)))? {
+        let at = options.time.resolve(duration);
+        let scale = options.width.map(|width| format!("scale={}:-2", width));
+        thumbnails.poster = Some(write_image(input, &poster, at, scale, options.format)?);
+    }
+
+    if let Some(frames) = options.contact_sheet.filter(|frames| *frames > 0) {
+        let sheet = output_dir.join(format!("{}_sheet.{}", stem, extension));
+        if let Some(sheet) = options.overwrite.resolve(&sheet)? {
+            let columns = (frames as f64).sqrt().ceil() as u32;
+            let rows = frames.div_ceil(columns);
+            let width = options.width.unwrap_or(DEFAULT_TILE_WIDTH);
+            // One frame from the middle of each of `frames` equal slices of the clip
+            let (start, rate) = match duration {
+                Some(duration) => {
+                    let slice = duration.as_secs_f64() / frames as f64;
+                    (Duration::from_secs_f64(slice / 2.0), format!("{}/{}", frames, duration.as_secs_f64()))
+                }
+                None => (Duration::ZERO, format!("1/{}", FALLBACK_SHEET_INTERVAL_SECS)),
+            };
+            let filter = format!("fps={},scale={}:-2,tile={}x{}", rate, width, columns, rows);
+            thumbnails.contact_sheet = Some(write_image(input, &sheet, start, Some(filter), options.format)?);
+        }
+    }
+    Ok(thumbnails)
+}
+
+/// Creates thumbnails for every input [`BatchOptions`] selects, one file after another.
+///
+/// Only the selection settings and `conversion.cancel` of `batch` are used.
+pub fn create_thumbnails_in_directory(
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    batch: &BatchOptions,
+    options: &ThumbnailOptions,
+) -> Result<Vec<ThumbnailEntry>, ConvertError> {
+    let mut entries = Vec::new();
+    for input in collect_inputs(input_dir, output_dir, batch)? {
+        if batch.conversion.cancel.is_cancelled() {
+            return Err(ConvertError::Cancelled);
+        }
+        if let Some(ref output_dir) = input.output_dir {
+            fs::create_dir_all(output_dir).map_err(|e| ConvertError::io(output_dir, e))?;
+        }
+        let result = create_thumbnails(&input.path, input.output_dir.as_deref(), options);
+        entries.push(ThumbnailEntry { input: input.path, result });
+    }
+    Ok(entries)
+}
+
+fn write_image(
+    input: &Path,
+    output: &Path,
+    at: Duration,
+    filter: Option<String>,
+    format: ImageFormat,
+) -> Result<PathBuf, ConvertError> {
+    let pending = PendingOutput::new(output);
+    let mut command = super::ffmpeg_command();
+    // Seeking before `-i` jumps to the nearest keyframe and decodes from there, which is fast and exact
+    command
+        .args(["-y", "-v", "error", "-ss"])
+        .arg(format!("{:.3}", at.as_secs_f64()))
+        .arg("-i")
+        .arg(input)
+        .args(["-map", "0:v:0", "-frames:v", "1"]);
+    if let Some(filter) = filter {
+        command.arg("-filter:v").arg(filter);
+    }
+    command.args(format.encoder_args()).args(["-f", "image2", "-update", "1"]).arg(pending.temp_path());
+    run_to_completion(command, input, output)?;
+    pending.commit()
+}
+
+// Image extraction takes a moment, so unlike conversions it runs without progress or cancellation
+fn run_to_completion(mut command: Command, input: &Path, output: &Path) -> Result<(), ConvertError> {
+    let result = command.stdin(Stdio::null()).output().map_err(|e| ConvertError::spawn(input, e))?;
+    if result.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    let tail = lines[lines.len().saturating_sub(super::STDERR_TAIL_LINES)..].join("\n");
+    Err(ConvertError::from_ffmpeg_exit(input, output, result.status, tail))
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+use std::time::Duration;
+
+/// The part of a clip to convert instead of all of it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: Duration,
+    /// Converts to the end of the clip when `None`.
+    pub end: Option<Duration>,
+}
+
+impl TimeRange {
+    /// A range from `start` (or the beginning) to `end` (or the end of the clip).
+    pub fn new(start: Option<Duration>, end: Option<Duration>) -> Result<Self, String> {
+        let range = TimeRange { start: start.unwrap_or_default(), end };
+        match range.end {
+            Some(end) if end <= range.start => Err(format!(
+                "the end {} is not after the start {}",
+                format_timestamp(end),
+                format_timestamp(range.start)
+            )),
+            _ => Ok(range),
+        }
+    }
+
+    /// A range of `duration` from `start` (or the beginning).
+    pub fn with_duration(start: Option<Duration>, duration: Duration) -> Result<Self, String> {
+        if duration.is_zero() {
+            return Err("the duration must be longer than zero".to_string());
+        }
+        let start = start.unwrap_or_default();
+        TimeRange::new(Some(start), Some(start + duration))
+    }
+
+    /// How long the trimmed clip is, given the length of the whole clip when it is known.
+    pub(crate) fn length(&self, clip: Option<Duration>) -> Option<Duration> {
+        let end = match (self.end, clip) {
+            (Some(end), Some(clip)) => end.min(clip),
+            (end, clip) => end.or(clip)?,
+        };
+        Some(end.saturating_sub(self.start))
+    }
+}
+
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{} to {}", format_timestamp(self.start), format_timestamp(end)),
+            None => write!(f, "{} to the end", format_timestamp(self.start)),
+        }
+    }
+}
+
+/// Parses seconds such as `12.5`, or `[hh:]mm:ss[.frac]`.
+pub fn parse_timestamp(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid time '{}' (expected e.g. 12.5, 01:30 or 1:02:03.5)", s);
+    let parts: Vec<&str> = s.trim().split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+    let mut secs = 0.0;
+    for part in parts {
+        let value: f64 = part.parse().map_err(|_| invalid())?;
+        secs = secs * 60.0 + value;
+    }
+    if !secs.is_finite() || secs < 0.0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs_f64(secs))
+}
+
+/// Formats a time as `HH:MM:SS.mmm`, the form ffmpeg prints.
+pub fn format_timestamp(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{:02}:{:02}:{:02}.{:03}", secs / 3600, secs / 60 % 60, secs % 60, time.subsec_millis())
+}
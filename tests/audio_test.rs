@@ -0,0 +1,88 @@
+mod common;
+
+use magic_converter::video::{ConversionPlan, MediaInfo, OutputFormat, StreamAction, StreamMode};
+use std::path::Path;
+
+// A recording with a second, commentary audio track
+const AVCHD_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "ac3" },
+    { "index": 2, "codec_type": "audio", "codec_name": "aac" },
+    { "index": 3, "codec_type": "subtitle", "codec_name": "hdmv_pgs_subtitle" }
+], "format": { "format_name": "mpegts", "duration": "10.0" } }"#;
+
+fn actions(format: OutputFormat) -> Vec<StreamAction> {
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap();
+    let plan = ConversionPlan::for_format(&info, format, StreamMode::Auto);
+    plan.streams.iter().map(|stream| stream.action.clone()).collect()
+}
+
+fn transcode(encoder: &str) -> StreamAction {
+    StreamAction::Transcode { encoder: encoder.to_string() }
+}
+
+#[test]
+fn test_audio_formats() {
+    let audio: Vec<_> = OutputFormat::ALL.into_iter().filter(|format| format.is_audio_only()).collect();
+    assert_eq!(audio, [OutputFormat::M4a, OutputFormat::Mp3, OutputFormat::Flac, OutputFormat::Wav, OutputFormat::Opus]);
+    assert_eq!("AAC".parse::<OutputFormat>(), Ok(OutputFormat::M4a));
+    assert_eq!(OutputFormat::Opus.extension(), "opus");
+}
+
+#[test]
+fn test_m4a_keeps_every_audio_track() {
+    let drop = StreamAction::Drop;
+    assert_eq!(actions(OutputFormat::M4a), [drop.clone(), transcode("aac"), StreamAction::Copy, drop]);
+}
+
+#[test]
+fn test_single_stream_formats_keep_the_first_track() {
+    let drop = StreamAction::Drop;
+    assert_eq!(actions(OutputFormat::Mp3), [drop.clone(), transcode("libmp3lame"), drop.clone(), drop.clone()]);
+    assert_eq!(actions(OutputFormat::Flac), [drop.clone(), transcode("flac"), drop.clone(), drop.clone()]);
+    assert_eq!(actions(OutputFormat::Wav), [drop.clone(), transcode("pcm_s16le"), drop.clone(), drop]);
+}
+
+#[test]
+fn test_silent_video_has_nothing_to_extract() {
+    let json = r#"{ "streams": [{ "index": 0, "codec_type": "video", "codec_name": "h264" }] }"#;
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), json).unwrap();
+    let plan = ConversionPlan::for_format(&info, OutputFormat::Opus, StreamMode::Auto);
+    assert_eq!(plan.kept().count(), 0);
+}
+
+#[test]
+fn test_normalization_reencodes_copied_audio() {
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap();
+    let plan = ConversionPlan::for_format(&info, OutputFormat::Mkv, StreamMode::Copy)
+        .with_audio_reencoded(OutputFormat::Mkv);
+    let actions: Vec<_> = plan.streams.iter().map(|stream| stream.action.clone()).collect();
+    assert_eq!(actions, [StreamAction::Copy, transcode("aac"), transcode("aac"), StreamAction::Copy]);
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so this needs a POSIX shell
+#[cfg(unix)]
+#[test]
+fn test_extract_normalized_mp3() {
+    use magic_converter::video::{convert_mts_to_mp4_with_options, Conversion, ConversionOptions};
+    use std::fs;
+
+    common::stand_in_tools_with_probe(AVCHD_PROBE);
+    let dir = tempfile::tempdir().unwrap();
+    let input = common::create_input(dir.path(), "args.mts");
+    let options =
+        ConversionOptions { format: OutputFormat::Mp3, normalize_loudness: true, ..ConversionOptions::default() };
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+    let Ok(Conversion::Converted { output, .. }) = result else {
+        panic!("Expected a conversion, got {:?}", result);
+    };
+    assert_eq!(output, dir.path().join("args.mp3"));
+    let args = fs::read_to_string(output).unwrap();
+    let args: Vec<&str> = args.lines().collect();
+    let has = |expected: &[&str]| args.windows(expected.len()).any(|w| w == expected);
+    assert!(has(&["-map", "0:1", "-c:0", "libmp3lame", "-filter:0"]), "{:?}", args);
+    assert!(args.iter().any(|arg| arg.starts_with("loudnorm=I=-23")), "{:?}", args);
+    assert_eq!(args.iter().filter(|arg| **arg == "-map").count(), 1, "{:?}", args);
+    assert!(has(&["-f", "mp3"]), "{:?}", args);
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use magic_converter::video::avchd::clip_info_for;
+use magic_converter::video::{read_clip_info, read_playlist, Camera, RecordingTime};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::tempdir;
+
+const SONY: Camera = Camera { maker_id: 0x0108, model_code: 0x0415 };
+// 2023-07-14 09:30:12 in BCD
+const RECORDED: [u8; 7] = [0x20, 0x23, 0x07, 0x14, 0x09, 0x30, 0x12];
+
+// Blu-ray extension data with a single AVCHD block tagged `tag`
+fn extension_data(tag: &[u8; 4], camera: Camera, recorded: [u8; 7]) -> Vec<u8> {
+    let mut meta = vec![0u8; 48];
+    meta[4..6].copy_from_slice(&camera.maker_id.to_be_bytes());
+    meta[6..8].copy_from_slice(&camera.model_code.to_be_bytes());
+    meta[41..48].copy_from_slice(&recorded);
+
+    let mut block = tag.to_vec();
+    block.extend([0; 4]);
+    block.extend(12u32.to_be_bytes());
+    block.extend(meta);
+
+    let mut data = Vec::new();
+    data.extend((20 + block.len() as u32).to_be_bytes());
+    data.extend(24u32.to_be_bytes());
+    data.extend([0, 0, 0, 1]);
+    data.extend([0, 2, 0, 1]);
+    data.extend(24u32.to_be_bytes());
+    data.extend((block.len() as u32).to_be_bytes());
+    data.extend(block);
+    data
+}
+
+// A playlist of (clip, connection condition) play items, each 10 seconds long
+fn playlist(items: &[(&str, u8)]) -> Vec<u8> {
+    let mut list = Vec::new();
+    list.extend([0; 4]);
+    list.extend([0; 2]);
+    list.extend((items.len() as u16).to_be_bytes());
+    list.extend([0; 2]);
+    for (clip, connection) in items {
+        list.extend(20u16.to_be_bytes());
+        list.extend(clip.as_bytes());
+        list.extend(b"M2TS");
+        list.extend([0, *connection, 0]);
+        list.extend(45_000u32.to_be_bytes());
+        list.extend(495_000u32.to_be_bytes());
+    }
+    let extension_start = 40 + list.len() as u32;
+
+    let mut data = b"MPLS0100".to_vec();
+    data.extend(40u32.to_be_bytes());
+    data.extend(0u32.to_be_bytes());
+    data.extend(extension_start.to_be_bytes());
+    data.resize(40, 0);
+    data.extend(list);
+    data.extend(extension_data(b"PLEX", SONY, RECORDED));
+    data
+}
+
+fn clip_info(recorded: [u8; 7]) -> Vec<u8> {
+    let mut data = b"HDMV0100".to_vec();
+    data.resize(24, 0);
+    data.extend(40u32.to_be_bytes());
+    data.resize(40, 0);
+    data.extend(extension_data(b"CLEX", SONY, recorded));
+    data
+}
+
+// `PRIVATE/AVCHD/BDMV` with the given clips, the first two of them one split recording
+fn card(root: &Path, clips: &[&str]) -> PathBuf {
+    let bdmv = root.join("PRIVATE").join("AVCHD").join("BDMV");
+    for dir in ["STREAM", "CLIPINF", "PLAYLIST"] {
+        fs::create_dir_all(bdmv.join(dir)).unwrap();
+    }
+    let mut items = Vec::new();
+    for (index, clip) in clips.iter().enumerate() {
+        fs::write(bdmv.join("STREAM").join(format!("{}.MTS", clip)), b"HDMV").unwrap();
+        let mut recorded = RECORDED;
+        recorded[6] += index as u8;
+        fs::write(bdmv.join("CLIPINF").join(format!("{}.CPI", clip)), clip_info(recorded)).unwrap();
+        items.push((*clip, if index == 1 { 5 } else { 1 }));
+    }
+    fs::write(bdmv.join("PLAYLIST").join("00000.MPL"), playlist(&items)).unwrap();
+    bdmv.join("STREAM")
+}
+
+#[test]
+fn test_read_playlist() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("00000.MPL");
+    fs::write(&path, playlist(&[("00000", 1), ("00001", 5), ("00002", 1)])).unwrap();
+
+    let playlist = read_playlist(&path).unwrap();
+    assert_eq!(playlist.items.len(), 3);
+    assert_eq!(playlist.items[0].clip, "00000");
+    assert_eq!((playlist.items[0].in_time, playlist.items[0].out_time), (Duration::from_secs(1), Duration::from_secs(11)));
+    assert_eq!(playlist.recordings(), [vec!["00000", "00001"], vec!["00002"]]);
+    assert_eq!(playlist.camera, Some(SONY));
+    assert_eq!(playlist.recorded.unwrap().to_string(), "2023-07-14 09:30:12");
+}
+
+#[test]
+fn test_read_clip_info() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("00000.CPI");
+    fs::write(&path, clip_info(RECORDED)).unwrap();
+
+    let info = read_clip_info(&path).unwrap();
+    let camera = info.camera.unwrap();
+    assert_eq!((camera.maker_name(), camera.model_code_hex()), (String::from("Sony"), String::from("0415")));
+    assert_eq!(camera.to_string(), "Sony, model code 0415");
+    assert_eq!(Camera { maker_id: 0x2222, ..camera }.maker_name(), "2222");
+    let recorded = RecordingTime { year: 2023, month: 7, day: 14, hour: 9, minute: 30, second: 12 };
+    assert_eq!(info.recorded, Some(recorded));
+}
+
+#[test]
+fn test_unset_clock_is_no_recording_time() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("00000.CPI");
+    fs::write(&path, clip_info([0; 7])).unwrap();
+    assert_eq!(read_clip_info(&path).unwrap().recorded, None);
+}
+
+#[test]
+fn test_other_files_are_rejected() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("00000.MPL");
+    fs::write(&path, b"not a playlist").unwrap();
+    assert!(read_playlist(&path).is_err());
+    assert!(read_clip_info(&path).is_err());
+}
+
+#[test]
+fn test_clip_info_is_found_next_to_the_stream() {
+    let dir = tempdir().unwrap();
+    let stream = card(dir.path(), &["00000", "00001"]);
+    let info = clip_info_for(&stream.join("00001.MTS")).unwrap();
+    assert_eq!(info.recorded.unwrap().to_string(), "2023-07-14 09:30:13");
+    assert_eq!(clip_info_for(&dir.path().join("00001.MTS")), None);
+}
+
+// Runs the converter against a stand-in ffmpeg script, so this needs a POSIX shell
+#[cfg(unix)]
+#[test]
+fn test_directory_conversion_follows_the_playlist() {
+    use magic_converter::video::{convert_mts_files_in_directory_with_options, BatchOptions, ConversionOptions};
+
+    // Without ffprobe, only the playlist can tell that the first two clips belong together
+    common::stand_in_tools();
+    let dir = tempdir().unwrap();
+    let stream = card(dir.path(), &["00000", "00001", "00002"]);
+    let output_dir = tempdir().unwrap();
+    let conversion =
+        ConversionOptions { output_name: "{date}_{time}".parse().unwrap(), ..ConversionOptions::default() };
+    let options = BatchOptions { conversion, ..BatchOptions::default() };
+
+    let report =
+        convert_mts_files_in_directory_with_options(&stream, Some(output_dir.path()), &options, None).unwrap();
+    assert_eq!(report.entries.len(), 2);
+    assert_eq!(report.entries[0].joined, [stream.join("00001.MTS")]);
+    assert_eq!(
+        common::dir_entries(output_dir.path()),
+        [".magic-converter-journal.json", "2023-07-14_09-30-12.mp4", "2023-07-14_09-30-14.mp4"]
+    );
+}
@@ -0,0 +1,179 @@
+// Runs the converter against a stand-in ffmpeg script, so these tests need a POSIX shell.
+#![cfg(unix)]
+
+mod common;
+
+use common::{create_input, stand_in_tools};
+use magic_converter::video::batch::default_workers;
+use magic_converter::video::{
+    convert_mts_files_in_directory_with_options, BatchOptions, ConvertError, FileOutcome, ProgressEvent,
+};
+use glob::Pattern;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use tempfile::tempdir;
+
+#[test]
+fn test_parallel_directory_conversion() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    for index in 0..8 {
+        create_input(input_dir.path(), &format!("{:05}.MTS", index));
+    }
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    let options = BatchOptions { workers: 4, ..BatchOptions::default() };
+    convert_mts_files_in_directory_with_options(
+        input_dir.path(),
+        Some(output_dir.path()),
+        &options,
+        Some(Arc::new(move |event: &ProgressEvent| sink.lock().unwrap().push(event.clone()))),
+    )
+    .unwrap();
+
+    for index in 0..8 {
+        assert!(output_dir.path().join(format!("{:05}.mp4", index)).exists());
+    }
+
+    let events = events.lock().unwrap();
+    let overall: Vec<f32> = events.iter().map(|event| event.overall_progress).collect();
+    assert!(overall.windows(2).all(|pair| pair[0] <= pair[1]), "Overall progress should never move backwards");
+    assert_eq!(overall.last(), Some(&1.0));
+
+    let indices: HashSet<usize> = events.iter().map(|event| event.file_index).collect();
+    assert_eq!(indices, (0..8).collect());
+    assert!(events.iter().all(|event| event.file_count == 8));
+    for event in events.iter() {
+        let expected = format!("{:05}.MTS", event.file_index);
+        assert!(event.file.ends_with(&expected), "Per-file events should keep their own file");
+    }
+}
+
+#[test]
+fn test_parallel_conversion_reports_failure() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a.mts");
+    create_input(input_dir.path(), "b-broken.mts");
+    create_input(input_dir.path(), "c.mts");
+
+    let options = BatchOptions { workers: 2, ..BatchOptions::default() };
+    let result = convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None);
+    match result {
+        Err(ConvertError::FfmpegFailed { path, .. }) => assert!(path.ends_with("b-broken.mts")),
+        other => panic!("Expected the broken file to fail, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_continue_on_error_reports_every_file() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a.mts");
+    create_input(input_dir.path(), "b-broken.mts");
+    create_input(input_dir.path(), "c.mts");
+
+    let options = BatchOptions { workers: 2, continue_on_error: true, ..BatchOptions::default() };
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None).unwrap();
+
+    assert_eq!(report.entries.len(), 3);
+    assert_eq!((report.converted(), report.skipped(), report.failed()), (2, 0, 1));
+    assert!(report.has_failures());
+
+    let inputs: Vec<_> = report.entries.iter().map(|entry| entry.input.file_name().unwrap().to_owned()).collect();
+    assert_eq!(inputs, ["a.mts", "b-broken.mts", "c.mts"], "Entries should be in input order");
+
+    match &report.entries[0].outcome {
+        FileOutcome::Converted { output, output_size, .. } => {
+            assert_eq!(output, &output_dir.path().join("a.mp4"));
+            assert_eq!(*output_size, 3);
+        }
+        other => panic!("Expected a.mts to convert, got {:?}", other),
+    }
+    assert!(matches!(
+        report.entries[1].outcome,
+        FileOutcome::Failed { error: ConvertError::FfmpegFailed { .. } }
+    ));
+    assert!(output_dir.path().join("c.mp4").exists());
+}
+
+#[test]
+fn test_stop_on_error_skips_remaining_files() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a-broken.mts");
+    create_input(input_dir.path(), "b.mts");
+
+    let options = BatchOptions { workers: 1, ..BatchOptions::default() };
+    let result = convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None);
+
+    assert!(matches!(result, Err(ConvertError::FfmpegFailed { .. })));
+    assert!(!output_dir.path().join("b.mp4").exists(), "Files after the failure should not be converted");
+}
+
+#[test]
+fn test_recursive_conversion_mirrors_folders() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    let stream_dir = input_dir.path().join("2023-05-01/PRIVATE/AVCHD/BDMV/STREAM");
+    fs::create_dir_all(&stream_dir).unwrap();
+    fs::create_dir_all(input_dir.path().join("2023-06-12")).unwrap();
+    create_input(input_dir.path(), "top.mts");
+    create_input(&stream_dir, "00000.MTS");
+    create_input(&input_dir.path().join("2023-06-12"), "party.mts");
+
+    let flat = BatchOptions::default();
+    let report = convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &flat, None)
+        .unwrap();
+    assert_eq!(report.entries.len(), 1, "Only top-level files are converted without recursion");
+
+    let output_dir = tempdir().unwrap();
+    let recursive = BatchOptions { recursive: true, ..BatchOptions::default() };
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &recursive, None)
+            .unwrap();
+    assert_eq!(report.converted(), 3);
+    assert!(output_dir.path().join("top.mp4").exists());
+    assert!(output_dir.path().join("2023-05-01/PRIVATE/AVCHD/BDMV/STREAM/00000.mp4").exists());
+    assert!(output_dir.path().join("2023-06-12/party.mp4").exists());
+}
+
+#[test]
+fn test_include_and_exclude_patterns() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    let stream_dir = input_dir.path().join("PRIVATE/AVCHD/BDMV/STREAM");
+    fs::create_dir_all(&stream_dir).unwrap();
+    create_input(input_dir.path(), "top.mts");
+    create_input(&stream_dir, "00000.MTS");
+    create_input(&stream_dir, "00001.MTS");
+
+    let options = BatchOptions {
+        recursive: true,
+        include: vec![Pattern::new("**/stream/*.mts").unwrap()],
+        exclude: vec![Pattern::new("**/00001.*").unwrap()],
+        ..BatchOptions::default()
+    };
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None).unwrap();
+
+    let converted: Vec<_> = report.entries.iter().map(|entry| entry.input.file_name().unwrap().to_owned()).collect();
+    assert_eq!(converted, ["00000.MTS"]);
+    assert!(output_dir.path().join("PRIVATE/AVCHD/BDMV/STREAM/00000.mp4").exists());
+    assert!(!output_dir.path().join("top.mp4").exists());
+}
+
+#[test]
+fn test_default_workers() {
+    assert!(default_workers() >= 1);
+    assert_eq!(BatchOptions::default().workers, default_workers());
+}
@@ -0,0 +1,115 @@
+// Runs the converter against a stand-in ffmpeg script, so these tests need a POSIX shell.
+#![cfg(unix)]
+
+mod common;
+
+use common::{create_input, dir_entries, stand_in_tools};
+use magic_converter::video::{
+    convert_mts_files_in_directory_with_options, convert_mts_to_mp4_with_options, BatchOptions, CancellationToken,
+    ConversionOptions, ConvertError, FileOutcome,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+#[test]
+fn test_cancel_kills_ffmpeg_and_removes_partial_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "slow.mts");
+    let output = dir.path().join("slow.mp4");
+
+    let options = ConversionOptions::default();
+    let cancel = options.cancel.clone();
+    let canceller = thread::spawn({
+        let dir = dir.path().to_path_buf();
+        move || {
+            // Wait for ffmpeg to start writing before cancelling
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while dir_entries(&dir).len() < 2 && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(10));
+            }
+            cancel.cancel();
+        }
+    });
+
+    let started = Instant::now();
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+    canceller.join().unwrap();
+
+    assert!(matches!(result, Err(ConvertError::Cancelled)), "Expected Cancelled, got {:?}", result);
+    assert!(started.elapsed() < Duration::from_secs(20), "ffmpeg should be killed, not waited for");
+    assert!(!output.exists(), "Partial output should be removed");
+    assert_eq!(dir_entries(dir.path()), ["slow.mts"], "Temporary output should be removed");
+}
+
+#[test]
+fn test_cancelled_token_stops_before_starting() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "clip.mts");
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let options = ConversionOptions { cancel, ..ConversionOptions::default() };
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+    assert!(matches!(result, Err(ConvertError::Cancelled)));
+    assert!(!dir.path().join("clip.mp4").exists());
+}
+
+#[test]
+fn test_cancel_stops_directory_conversion() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "first.mts");
+    create_input(input_dir.path(), "second.mts");
+
+    let options = BatchOptions::default();
+    options.conversion.cancel.cancel();
+
+    let report = convert_mts_files_in_directory_with_options(
+        input_dir.path(),
+        Some(output_dir.path()),
+        &options,
+        None,
+    )
+    .unwrap();
+    assert_eq!(report.entries.len(), 2, "The report should still list every file");
+    assert_eq!(report.cancelled(), 2);
+    assert_eq!(std::fs::read_dir(output_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_cancel_during_directory_conversion_keeps_the_report() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    for name in ["a.mts", "b_slow.mts", "c.mts"] {
+        create_input(input_dir.path(), name);
+    }
+
+    let options = BatchOptions { workers: 1, ..BatchOptions::default() };
+    let cancel = options.conversion.cancel.clone();
+    let canceller = thread::spawn({
+        let output_dir = output_dir.path().to_path_buf();
+        move || {
+            // Wait for the slow file to start before cancelling
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while !dir_entries(&output_dir).iter().any(|name| name.contains("b_slow")) && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(10));
+            }
+            cancel.cancel();
+        }
+    });
+
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None)
+            .unwrap();
+    canceller.join().unwrap();
+
+    let outcomes: Vec<_> = report.entries.iter().map(|entry| &entry.outcome).collect();
+    assert!(matches!(outcomes[..], [FileOutcome::Converted { .. }, FileOutcome::Cancelled, FileOutcome::Cancelled]));
+    assert!(!report.has_failures());
+}
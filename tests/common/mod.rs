@@ -0,0 +1,120 @@
+// Shared stand-ins for ffmpeg and ffprobe, so conversion behaviour can be tested without FFmpeg installed.
+// They are `sh` scripts, so every test that uses them only runs on Unix.
+#![cfg(unix)]
+#![allow(dead_code)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tempfile::{tempdir, TempDir};
+
+pub const NOISE_LINES: usize = 50000;
+
+// Behaviour is picked by the input file name:
+// - `noisy`: writes ~4 MB to stderr before any progress output
+// - `broken`: fails like ffmpeg does on an undecodable input
+// - `slow`: writes a partial output and then hangs until killed
+// - `empty`: succeeds after creating an empty output
+// - `args`: writes its arguments, one per line, as the output
+const STAND_IN_FFMPEG: &str = r#"#!/bin/sh
+prev=""
+for arg; do
+    if [ "$prev" = "-i" ]; then input="$arg"; fi
+    prev="$arg"
+    last="$arg"
+done
+case "$input" in
+    *noisy*)
+        i=0
+        while [ $i -lt 50000 ]; do
+            echo "[mpegts @ 0x0] noise line $i: PES packet size mismatch, skipping damaged packet" >&2
+            i=$((i + 1))
+        done
+        ;;
+esac
+echo "frame=10"
+echo "progress=continue"
+case "$input" in
+    *broken*)
+        echo "$input: Invalid data found when processing input" >&2
+        exit 1
+        ;;
+    *slow*)
+        printf 'partial' > "$last"
+        exec sleep 30
+        ;;
+    *args*)
+        printf '%s\n' "$@" > "$last"
+        echo "progress=end"
+        exit 0
+        ;;
+    *empty*)
+        : > "$last"
+        echo "progress=end"
+        exit 0
+        ;;
+esac
+printf 'mp4' > "$last"
+echo "progress=end"
+"#;
+
+/// Points the converter at the stand-in ffmpeg for the rest of this test binary.
+pub fn stand_in_tools() -> &'static TempDir {
+    static TOOLS: OnceLock<TempDir> = OnceLock::new();
+    TOOLS.get_or_init(|| {
+        let dir = tempdir().unwrap();
+        let ffmpeg = dir.path().join("ffmpeg");
+        fs::write(&ffmpeg, STAND_IN_FFMPEG).unwrap();
+        fs::set_permissions(&ffmpeg, fs::Permissions::from_mode(0o755)).unwrap();
+        std::env::set_var("MAGIC_CONVERTER_FFMPEG", &ffmpeg);
+        std::env::set_var("MAGIC_CONVERTER_FFPROBE", dir.path().join("missing-ffprobe"));
+        dir
+    })
+}
+
+/// Like [`stand_in_tools`], but with an ffprobe that prints `json` for every input.
+pub fn stand_in_tools_with_probe(json: &str) -> &'static TempDir {
+    stand_in_tools_with_probes(&[("*", Some(json))])
+}
+
+/// Like [`stand_in_tools`], but with an ffprobe that tells inputs apart by name. Each input gets the JSON of the
+/// first `(pattern, json)` pair whose `sh` case pattern, such as `*00001*` or `*notes*|*.MOI`, matches its path.
+/// A `None` JSON, or no match at all, fails the way ffprobe does on a file that is not media.
+///
+/// The probe is set up once per test binary, so every test in it should pass the same pairs.
+pub fn stand_in_tools_with_probes(probes: &[(&str, Option<&str>)]) -> &'static TempDir {
+    static PROBE: OnceLock<()> = OnceLock::new();
+    let tools = stand_in_tools();
+    PROBE.get_or_init(|| {
+        let mut script = String::from("#!/bin/sh\nfor arg; do last=\"$arg\"; done\ncase \"$last\" in\n");
+        for (pattern, json) in probes {
+            match json {
+                Some(json) => script.push_str(&format!("    {}) cat <<'EOF'\n{}\nEOF\n        ;;\n", pattern, json)),
+                None => script.push_str(&format!("    {}) exit 1 ;;\n", pattern)),
+            }
+        }
+        script.push_str("    *) exit 1 ;;\nesac\n");
+        let ffprobe = tools.path().join("ffprobe");
+        fs::write(&ffprobe, script).unwrap();
+        fs::set_permissions(&ffprobe, fs::Permissions::from_mode(0o755)).unwrap();
+        std::env::set_var("MAGIC_CONVERTER_FFPROBE", &ffprobe);
+    });
+    tools
+}
+
+/// Names of everything in `dir`, sorted, including hidden temporary files.
+pub fn dir_entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn create_input(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, b"HDMV").unwrap();
+    path
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use magic_converter::video::{
+    AudioCodec, ConversionOptions, ConversionPlan, EncodingSettings, MediaInfo, Preset, Quality, StreamAction,
+    StreamMode, VideoCodec, VideoSettings,
+};
+use std::path::Path;
+
+const AVCHD_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080 },
+    { "index": 1, "codec_type": "audio", "codec_name": "aac" }
+], "format": { "format_name": "mpegts", "duration": "10.0" } }"#;
+
+#[test]
+fn test_preset_names_round_trip() {
+    for preset in Preset::ALL {
+        assert_eq!(preset.to_string().parse::<Preset>(), Ok(preset));
+    }
+    assert!("share-1080p".parse::<Preset>().is_err());
+}
+
+#[test]
+fn test_codec_names_and_aliases() {
+    for codec in VideoCodec::ALL {
+        assert_eq!(codec.to_string().parse::<VideoCodec>(), Ok(codec));
+    }
+    assert_eq!("x265".parse::<VideoCodec>(), Ok(VideoCodec::H265));
+    assert_eq!("HEVC".parse::<VideoCodec>(), Ok(VideoCodec::H265));
+    assert_eq!("svt-av1".parse::<VideoCodec>(), Ok(VideoCodec::Av1Svt));
+    assert_eq!(VideoCodec::Vp9.encoder(), "libvpx-vp9");
+    assert_eq!("libopus".parse::<AudioCodec>(), Ok(AudioCodec::Opus));
+}
+
+#[test]
+fn test_presets() {
+    let mut options = ConversionOptions::default();
+    options.apply_preset(Preset::ArchiveCopy);
+    assert_eq!(options.stream_mode, StreamMode::Copy);
+    assert_eq!(options.encoding, EncodingSettings::default());
+
+    options.apply_preset(Preset::Share720p);
+    assert_eq!(options.stream_mode, StreamMode::Auto);
+    let video = options.encoding.video.unwrap();
+    assert_eq!((video.codec, video.quality, video.max_height), (VideoCodec::H264, Quality::Crf(23), Some(720)));
+    assert_eq!(options.encoding.audio.unwrap().bitrate_kbps, Some(128));
+
+    let web = Preset::WebSmall.encoding().video.unwrap();
+    assert_eq!((web.max_height, web.fps), (Some(480), Some(30.0)));
+}
+
+#[test]
+fn test_encoding_settings_force_transcoding() {
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap();
+    let encoding = EncodingSettings {
+        video: Some(VideoSettings { codec: VideoCodec::H265, ..VideoSettings::default() }),
+        audio: None,
+    };
+    let plan = ConversionPlan::for_mp4(&info, StreamMode::Auto).with_encoding(&encoding);
+    let actions: Vec<_> = plan.streams.iter().map(|stream| stream.action.clone()).collect();
+    assert_eq!(actions, [StreamAction::Transcode { encoder: String::from("libx265") }, StreamAction::Copy]);
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so this needs a POSIX shell
+#[cfg(unix)]
+#[test]
+fn test_preset_encoder_arguments() {
+    use magic_converter::video::{convert_mts_to_mp4_with_options, Conversion};
+    use std::fs;
+
+    common::stand_in_tools_with_probe(AVCHD_PROBE);
+    let dir = tempfile::tempdir().unwrap();
+    let input = common::create_input(dir.path(), "args.mts");
+    let mut options = ConversionOptions::default();
+    options.apply_preset(Preset::Share720p);
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+    let Ok(Conversion::Converted { output, .. }) = result else {
+        panic!("Expected a conversion, got {:?}", result);
+    };
+    let args = fs::read_to_string(output).unwrap();
+    let args: Vec<&str> = args.lines().collect();
+    let has = |expected: &[&str]| args.windows(expected.len()).any(|w| w == expected);
+    assert!(has(&["-c:0", "libx264", "-crf:0", "23", "-preset:0", "medium"]), "{:?}", args);
+    assert!(has(&["-filter:0", "scale=-2:'min(720,ih)'"]), "{:?}", args);
+    assert!(has(&["-c:1", "aac", "-b:1", "128k"]), "{:?}", args);
+    assert!(!args.contains(&"aac_adtstoasc"), "re-encoded audio needs no bitstream filter");
+}
@@ -0,0 +1,53 @@
+use magic_converter::video::ConvertError;
+use std::error::Error;
+use std::io;
+use std::path::PathBuf;
+
+#[test]
+fn test_error_messages() {
+    assert_eq!(
+        ConvertError::InputNotFound(PathBuf::from("clip.mts")).to_string(),
+        "input not found: clip.mts"
+    );
+    assert_eq!(
+        ConvertError::OutputExists(PathBuf::from("out/clip.mp4")).to_string(),
+        "output already exists: out/clip.mp4"
+    );
+    assert_eq!(
+        ConvertError::UnsupportedCodec {
+            path: PathBuf::from("clip.mts"),
+            codec: Some(String::from("pcm_bluray")),
+            stderr_tail: String::new(),
+        }
+        .to_string(),
+        "unsupported codec pcm_bluray in clip.mts"
+    );
+    assert!(ConvertError::FfmpegNotFound.to_string().contains("ffmpeg was not found"));
+}
+
+#[test]
+fn test_io_error_keeps_source() {
+    let error = ConvertError::Io {
+        path: PathBuf::from("clips"),
+        source: io::Error::new(io::ErrorKind::PermissionDenied, "permission denied"),
+    };
+    assert_eq!(error.to_string(), "clips: permission denied");
+    assert!(error.source().is_some());
+    assert!(error.stderr_tail().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_ffmpeg_failure_reports_exit_code_and_last_line() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let error = ConvertError::FfmpegFailed {
+        path: PathBuf::from("clip.mts"),
+        status: std::process::ExitStatus::from_raw(1 << 8),
+        stderr_tail: String::from("[mpegts] probing\nclip.mts: Invalid data found when processing input\n"),
+    };
+    assert_eq!(
+        error.to_string(),
+        "ffmpeg exited with code 1 while converting clip.mts: clip.mts: Invalid data found when processing input"
+    );
+}
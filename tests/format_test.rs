@@ -0,0 +1,155 @@
+mod common;
+
+use magic_converter::video::{
+    AudioCodec, AudioSettings, ConversionPlan, ConvertError, EncodingSettings, MediaInfo, OutputFormat, StreamAction,
+    StreamKind, StreamMode, VideoCodec, VideoSettings,
+};
+use std::path::Path;
+
+const AVCHD_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "ac3" },
+    { "index": 2, "codec_type": "subtitle", "codec_name": "hdmv_pgs_subtitle" }
+], "format": { "format_name": "mpegts", "duration": "10.0" } }"#;
+
+fn avchd() -> MediaInfo {
+    MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap()
+}
+
+fn actions(plan: &ConversionPlan) -> Vec<StreamAction> {
+    plan.streams.iter().map(|stream| stream.action.clone()).collect()
+}
+
+fn transcode(encoder: &str) -> StreamAction {
+    StreamAction::Transcode { encoder: encoder.to_string() }
+}
+
+fn video(codec: VideoCodec) -> EncodingSettings {
+    EncodingSettings { video: Some(VideoSettings { codec, ..VideoSettings::default() }), audio: None }
+}
+
+#[test]
+fn test_format_names_round_trip() {
+    for format in OutputFormat::ALL {
+        assert_eq!(format.to_string().parse::<OutputFormat>(), Ok(format));
+    }
+    assert_eq!("Matroska".parse::<OutputFormat>(), Ok(OutputFormat::Mkv));
+    assert_eq!("fragmented-mp4".parse::<OutputFormat>(), Ok(OutputFormat::FragmentedMp4));
+    assert_eq!(OutputFormat::FragmentedMp4.extension(), "mp4");
+    assert!("avi".parse::<OutputFormat>().is_err());
+}
+
+#[test]
+fn test_mkv_keeps_everything() {
+    let plan = ConversionPlan::for_format(&avchd(), OutputFormat::Mkv, StreamMode::Auto);
+    assert_eq!(actions(&plan), [StreamAction::Copy, StreamAction::Copy, StreamAction::Copy]);
+}
+
+#[test]
+fn test_webm_transcodes_to_vp9_and_opus() {
+    let plan = ConversionPlan::for_format(&avchd(), OutputFormat::Webm, StreamMode::Copy);
+    assert_eq!(actions(&plan), [transcode("libvpx-vp9"), transcode("libopus"), StreamAction::Drop]);
+}
+
+#[test]
+fn test_copy_mode_reencodes_what_the_container_cannot_hold() {
+    let json = r#"{ "streams": [{ "index": 0, "codec_type": "audio", "codec_name": "pcm_bluray" }] }"#;
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), json).unwrap();
+    for format in [OutputFormat::Mp4, OutputFormat::Mkv, OutputFormat::Mov] {
+        let plan = ConversionPlan::for_format(&info, format, StreamMode::Copy);
+        assert_eq!(actions(&plan), [transcode("aac")], "{}", format);
+    }
+}
+
+#[test]
+fn test_text_subtitles_follow_the_container() {
+    let json = r#"{ "streams": [{ "index": 0, "codec_type": "subtitle", "codec_name": "mov_text" }] }"#;
+    let info = MediaInfo::from_ffprobe_json(Path::new("clip.mov"), json).unwrap();
+    let plan = |format| actions(&ConversionPlan::for_format(&info, format, StreamMode::Auto));
+    assert_eq!(plan(OutputFormat::Mov), [StreamAction::Copy]);
+    assert_eq!(plan(OutputFormat::Mkv), [transcode("srt")]);
+    assert_eq!(plan(OutputFormat::Webm), [transcode("webvtt")]);
+}
+
+#[test]
+fn test_codec_compatibility() {
+    assert!(OutputFormat::Mp4.plays(StreamKind::Video, "h264"));
+    assert!(!OutputFormat::Mp4.plays(StreamKind::Audio, "ac3"));
+    assert!(OutputFormat::Mp4.can_hold(StreamKind::Audio, "ac3"));
+    assert!(!OutputFormat::Webm.can_hold(StreamKind::Video, "h264"));
+
+    assert!(OutputFormat::Mkv.check_encoding(&video(VideoCodec::H265)).is_ok());
+    assert!(OutputFormat::Webm.check_encoding(&video(VideoCodec::Av1Svt)).is_ok());
+    let result = OutputFormat::Webm.check_encoding(&video(VideoCodec::H264));
+    assert!(
+        matches!(result, Err(ConvertError::IncompatibleCodec { ref codec, format: OutputFormat::Webm }) if codec == "h264"),
+        "got {:?}",
+        result
+    );
+    let mp3 = EncodingSettings { video: None, audio: Some(AudioSettings { codec: AudioCodec::Mp3, bitrate_kbps: None }) };
+    assert!(OutputFormat::Webm.check_encoding(&mp3).is_err());
+    assert!(OutputFormat::Mov.check_encoding(&mp3).is_ok());
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, stand_in_tools_with_probe};
+    use super::{video, AVCHD_PROBE};
+    use magic_converter::video::{
+        convert_mts_to_mp4_with_options, Conversion, ConversionOptions, ConvertError, OutputFormat, VideoCodec,
+    };
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn converted_args(format: OutputFormat, options: ConversionOptions) -> (String, Vec<String>) {
+        stand_in_tools_with_probe(AVCHD_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let options = ConversionOptions { format, ..options };
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+        let Ok(Conversion::Converted { output, .. }) = result else {
+            panic!("Expected a conversion, got {:?}", result);
+        };
+        let name = output.file_name().unwrap().to_string_lossy().into_owned();
+        (name, fs::read_to_string(output).unwrap().lines().map(String::from).collect())
+    }
+
+    fn has(args: &[String], expected: &[&str]) -> bool {
+        args.windows(expected.len()).any(|window| window == expected)
+    }
+
+    #[test]
+    fn test_mkv_output() {
+        let options = ConversionOptions { encoding: video(VideoCodec::H265), ..ConversionOptions::default() };
+        let (name, args) = converted_args(OutputFormat::Mkv, options);
+        assert_eq!(name, "args.mkv");
+        assert!(has(&args, &["-f", "matroska"]), "{:?}", args);
+        assert!(has(&args, &["-map", "0:2", "-c:2", "copy"]), "MKV keeps PGS subtitles: {:?}", args);
+        assert!(!args.iter().any(|arg| arg == "hvc1"), "the hvc1 tag is for the MP4 family: {:?}", args);
+    }
+
+    #[test]
+    fn test_fragmented_mp4_output() {
+        let (name, args) = converted_args(OutputFormat::FragmentedMp4, ConversionOptions::default());
+        assert_eq!(name, "args.mp4");
+        assert!(has(&args, &["-f", "mp4", "-movflags", "+frag_keyframe+empty_moov+default_base_moof"]), "{:?}", args);
+    }
+
+    #[test]
+    fn test_incompatible_codec_fails_before_ffmpeg_runs() {
+        stand_in_tools_with_probe(AVCHD_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let options = ConversionOptions {
+            format: OutputFormat::Webm,
+            encoding: video(VideoCodec::H264),
+            ..ConversionOptions::default()
+        };
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+        assert!(matches!(result, Err(ConvertError::IncompatibleCodec { .. })), "got {:?}", result);
+        assert!(!dir.path().join("args.webm").exists());
+    }
+}
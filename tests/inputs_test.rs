@@ -0,0 +1,167 @@
+mod common;
+
+use magic_converter::video::{MediaInfo, INPUT_EXTENSIONS};
+use std::path::Path;
+
+const RECORDING_PROBE: &str = r#"{ "streams": [{ "index": 0, "codec_type": "video", "codec_name": "mpeg2video" }],
+    "format": { "format_name": "mpeg", "duration": "12.5" } }"#;
+const PHOTO_PROBE: &str = r#"{ "streams": [{ "index": 0, "codec_type": "video", "codec_name": "mjpeg" }],
+    "format": { "format_name": "image2" } }"#;
+
+#[test]
+fn test_default_extensions_cover_older_cameras() {
+    for ext in ["mts", "m2ts", "ts", "mod", "tod", "avi", "mov", "mkv", "vob", "3gp", "wmv"] {
+        assert!(INPUT_EXTENSIONS.contains(&ext), "{} should be converted by default", ext);
+    }
+    assert!(!INPUT_EXTENSIONS.contains(&"mp4"), "MP4 is the output format");
+}
+
+#[test]
+fn test_is_recording() {
+    let recording = MediaInfo::from_ffprobe_json(Path::new("MOV001.MOD"), RECORDING_PROBE).unwrap();
+    assert!(recording.is_recording());
+    let photo = MediaInfo::from_ffprobe_json(Path::new("DSC001.JPG"), PHOTO_PROBE).unwrap();
+    assert!(!photo.is_recording());
+}
+
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, stand_in_tools_with_probes};
+    use super::{PHOTO_PROBE, RECORDING_PROBE};
+    use magic_converter::video::{
+        convert_mts_files_in_directory_with_options, convert_mts_to_mp4_with_options, BatchOptions, ConversionOptions,
+        ConvertError, OutputFormat, OverwritePolicy, JOURNAL_FILE_NAME,
+    };
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    // `photo` is a still image, `notes` and MOD sidecars are not media at all
+    fn tools() {
+        stand_in_tools_with_probes(&[
+            ("*photo*", Some(PHOTO_PROBE)),
+            ("*notes*|*.MOI", None),
+            ("*", Some(RECORDING_PROBE)),
+        ]);
+    }
+
+    fn converted_inputs(input_dir: &Path, options: &BatchOptions) -> Vec<String> {
+        let output_dir = tempdir().unwrap();
+        let report = convert_mts_files_in_directory_with_options(input_dir, Some(output_dir.path()), options, None)
+            .unwrap();
+        report
+            .entries
+            .iter()
+            .map(|entry| entry.input.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn card() -> tempfile::TempDir {
+        let dir = tempdir().unwrap();
+        for name in ["00001.MTS", "00002.m2ts", "MOV001.MOD", "MOV001.MOI", "clip.AVI", "photo.jpg", "notes.txt", "old.mp4"]
+        {
+            create_input(dir.path(), name);
+        }
+        dir
+    }
+
+    #[test]
+    fn test_default_extensions() {
+        tools();
+        let dir = card();
+        let inputs = converted_inputs(dir.path(), &BatchOptions::default());
+        assert_eq!(inputs, ["00001.MTS", "00002.m2ts", "MOV001.MOD", "clip.AVI"]);
+    }
+
+    #[test]
+    fn test_custom_extensions() {
+        tools();
+        let dir = card();
+        let options = BatchOptions { extensions: vec![String::from("mod")], ..BatchOptions::default() };
+        assert_eq!(converted_inputs(dir.path(), &options), ["MOV001.MOD"]);
+    }
+
+    #[test]
+    fn test_detect_by_content() {
+        tools();
+        let dir = card();
+        create_input(dir.path(), "VIDEO_TS.unknown");
+        let options = BatchOptions {
+            extensions: vec![String::from("mts")],
+            detect_by_content: true,
+            ..BatchOptions::default()
+        };
+        let inputs = converted_inputs(dir.path(), &options);
+        assert_eq!(inputs, ["00001.MTS", "00002.m2ts", "MOV001.MOD", "VIDEO_TS.unknown", "clip.AVI", "old.mp4"]);
+    }
+
+    #[test]
+    fn test_outputs_of_an_earlier_run_are_not_inputs() {
+        tools();
+        let dir = tempdir().unwrap();
+        create_input(dir.path(), "clip.MTS");
+        create_input(dir.path(), "trip.mov");
+        let conversion = ConversionOptions {
+            format: OutputFormat::Mkv,
+            overwrite: OverwritePolicy::Skip,
+            ..ConversionOptions::default()
+        };
+        let options = BatchOptions { conversion, ..BatchOptions::default() };
+
+        let inputs = |dir: &Path| -> Vec<String> {
+            let report = convert_mts_files_in_directory_with_options(dir, None, &options, None).unwrap();
+            report.entries.iter().map(|entry| entry.input.file_name().unwrap().to_string_lossy().into_owned()).collect()
+        };
+        assert_eq!(inputs(dir.path()), ["clip.MTS", "trip.mov"]);
+        assert_eq!(inputs(dir.path()), ["clip.MTS", "trip.mov"], "the first run's .mkv files are not picked up");
+        assert!(!dir.path().join("clip_1.mkv").exists());
+    }
+
+    #[test]
+    fn test_inputs_in_the_output_format_are_converted_into_another_directory() {
+        tools();
+        let dir = tempdir().unwrap();
+        create_input(dir.path(), "clip.mkv");
+        create_input(dir.path(), "trip.MKV");
+        let conversion = ConversionOptions { format: OutputFormat::Mkv, ..ConversionOptions::default() };
+        let options = BatchOptions { conversion, ..BatchOptions::default() };
+        assert_eq!(converted_inputs(dir.path(), &options), ["clip.mkv", "trip.MKV"]);
+
+        let options = BatchOptions { extensions: vec![String::from("mkv")], ..options };
+        assert_eq!(converted_inputs(dir.path(), &options), ["clip.mkv", "trip.MKV"], "--ext is honoured");
+    }
+
+    #[test]
+    fn test_output_directory_inside_the_input_directory_is_not_searched() {
+        tools();
+        let dir = tempdir().unwrap();
+        create_input(dir.path(), "clip.mov");
+        let output_dir = dir.path().join("converted");
+        let conversion = ConversionOptions {
+            format: OutputFormat::Mov,
+            overwrite: OverwritePolicy::Overwrite,
+            ..ConversionOptions::default()
+        };
+        let options = BatchOptions { conversion, recursive: true, ..BatchOptions::default() };
+
+        convert_mts_files_in_directory_with_options(dir.path(), Some(&output_dir), &options, None).unwrap();
+        // Without the journal, only the output directory's location keeps its files out
+        fs::remove_file(output_dir.join(JOURNAL_FILE_NAME)).unwrap();
+        let report =
+            convert_mts_files_in_directory_with_options(dir.path(), Some(&output_dir), &options, None).unwrap();
+        assert_eq!(report.entries.len(), 1);
+        assert!(!output_dir.join("converted").exists());
+    }
+
+    #[test]
+    fn test_output_never_replaces_input() {
+        tools();
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "clip.mp4");
+        let options = ConversionOptions { overwrite: OverwritePolicy::Overwrite, ..ConversionOptions::default() };
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+        assert!(matches!(result, Err(ConvertError::OutputExists(_))), "got {:?}", result);
+        assert_eq!(fs::read(&input).unwrap(), b"HDMV");
+    }
+}
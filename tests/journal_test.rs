@@ -0,0 +1,111 @@
+// Runs the converter against a stand-in ffmpeg script, so these tests need a POSIX shell.
+#![cfg(unix)]
+
+mod common;
+
+use common::{create_input, stand_in_tools};
+use magic_converter::video::{
+    convert_mts_files_in_directory_with_options, BatchOptions, ConversionOptions, FileOutcome, JobState, Journal,
+    OverwritePolicy, JOURNAL_FILE_NAME,
+};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn options(resume: bool) -> BatchOptions {
+    BatchOptions {
+        conversion: ConversionOptions { overwrite: OverwritePolicy::Overwrite, ..ConversionOptions::default() },
+        continue_on_error: true,
+        resume,
+        ..BatchOptions::default()
+    }
+}
+
+fn journal(output_dir: &Path) -> Journal {
+    Journal::load(&output_dir.join(JOURNAL_FILE_NAME)).unwrap()
+}
+
+#[test]
+fn test_batch_records_every_file_in_journal() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a.mts");
+    create_input(input_dir.path(), "broken.mts");
+
+    convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(false), None)
+        .unwrap();
+
+    let journal = journal(output_dir.path());
+    let converted = journal.entry("a.mts").unwrap();
+    assert_eq!(converted.state, JobState::Completed { output: output_dir.path().join("a.mp4") });
+    assert_eq!(converted.size, 4);
+    assert!(matches!(journal.entry("broken.mts").unwrap().state, JobState::Failed { .. }));
+}
+
+#[test]
+fn test_resume_skips_completed_and_retries_the_rest() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a.mts");
+    let interrupted = create_input(input_dir.path(), "b.mts");
+    create_input(input_dir.path(), "broken.mts");
+
+    convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(false), None)
+        .unwrap();
+    // Mark outputs so a reconversion is noticeable, and pretend the run died while converting `b.mts`
+    fs::write(output_dir.path().join("a.mp4"), "kept").unwrap();
+    let mut journal = journal(output_dir.path());
+    journal.record("b.mts", &interrupted, &[], JobState::InProgress).unwrap();
+
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(true), None)
+            .unwrap();
+
+    assert_eq!((report.converted(), report.skipped(), report.failed()), (1, 1, 1));
+    assert!(matches!(report.entries[0].outcome, FileOutcome::Skipped { .. }));
+    assert!(matches!(report.entries[1].outcome, FileOutcome::Converted { .. }));
+    assert_eq!(fs::read_to_string(output_dir.path().join("a.mp4")).unwrap(), "kept");
+}
+
+#[test]
+fn test_resume_reconverts_changed_inputs_and_missing_outputs() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    let changed = create_input(input_dir.path(), "a.mts");
+    create_input(input_dir.path(), "b.mts");
+
+    convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(false), None)
+        .unwrap();
+    fs::write(&changed, "HDMV and more").unwrap();
+    fs::remove_file(output_dir.path().join("b.mp4")).unwrap();
+
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(true), None)
+            .unwrap();
+    assert_eq!((report.converted(), report.skipped()), (2, 0));
+}
+
+#[test]
+fn test_without_resume_everything_is_converted_again() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a.mts");
+
+    convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(false), None)
+        .unwrap();
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options(false), None)
+            .unwrap();
+    assert_eq!(report.converted(), 1);
+}
+
+#[test]
+fn test_load_missing_journal_is_empty() {
+    let dir = tempdir().unwrap();
+    let journal = Journal::load(&dir.path().join(JOURNAL_FILE_NAME)).unwrap();
+    assert!(journal.entry("a.mts").is_none());
+}
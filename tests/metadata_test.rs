@@ -0,0 +1,100 @@
+mod common;
+
+use magic_converter::video::metadata::{format_creation_time, parse_creation_time};
+use std::time::{Duration, UNIX_EPOCH};
+
+// 2023-07-14 09:30:12 UTC
+const RECORDED_SECS: u64 = 1_689_327_012;
+
+const TAGGED_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "aac" }
+], "format": { "format_name": "mpegts", "duration": "10.0",
+    "tags": { "creation_time": "2023-07-14T09:30:12.000000Z" } } }"#;
+const UNTAGGED_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "aac" }
+], "format": { "format_name": "mpegts", "duration": "10.0" } }"#;
+
+#[test]
+fn test_parse_creation_time() {
+    let recorded = UNIX_EPOCH + Duration::from_secs(RECORDED_SECS);
+    assert_eq!(parse_creation_time("2023-07-14T09:30:12.000000Z"), Some(recorded));
+    assert_eq!(parse_creation_time("2023-07-14 09:30:12"), Some(recorded));
+    assert_eq!(parse_creation_time("2023-07-14T11:30:12+02:00"), Some(recorded));
+    assert_eq!(parse_creation_time("2023-07-14T04:30:12-05:00"), Some(recorded));
+    assert_eq!(parse_creation_time("2023-07-14T09:30:12.5Z"), Some(recorded + Duration::from_millis(500)));
+    assert_eq!(parse_creation_time("2023-13-14T09:30:12Z"), None);
+    assert_eq!(parse_creation_time("yesterday"), None);
+}
+
+#[test]
+fn test_format_creation_time() {
+    let recorded = UNIX_EPOCH + Duration::from_secs(RECORDED_SECS) + Duration::from_micros(250);
+    assert_eq!(format_creation_time(recorded), "2023-07-14T09:30:12.000250Z");
+    assert_eq!(format_creation_time(UNIX_EPOCH + Duration::from_secs(951_782_400)), "2000-02-29T00:00:00.000000Z");
+    assert_eq!(parse_creation_time(&format_creation_time(recorded)), Some(recorded));
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, stand_in_tools_with_probes};
+    use super::{RECORDED_SECS, TAGGED_PROBE, UNTAGGED_PROBE};
+    use magic_converter::video::{convert_mts_to_mp4_with_options, Conversion, ConversionOptions, MetadataTags};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tempfile::tempdir;
+
+    // Reports a creation time for every input but the `untagged` ones
+    fn tools() {
+        stand_in_tools_with_probes(&[("*untagged*", Some(UNTAGGED_PROBE)), ("*", Some(TAGGED_PROBE))]);
+    }
+
+    fn convert(input: &Path, options: &ConversionOptions) -> PathBuf {
+        let result = convert_mts_to_mp4_with_options(input, None, options, None);
+        let Ok(Conversion::Converted { output, .. }) = result else {
+            panic!("Expected a conversion, got {:?}", result);
+        };
+        output
+    }
+
+    fn modified(path: &Path) -> SystemTime {
+        fs::metadata(path).unwrap().modified().unwrap()
+    }
+
+    #[test]
+    fn test_recording_time_and_tags_are_written() {
+        tools();
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let tags = MetadataTags {
+            title: Some("Summer holiday".to_string()),
+            comment: None,
+            author: Some("Alex".to_string()),
+        };
+
+        let output = convert(&input, &ConversionOptions { metadata: tags, ..ConversionOptions::default() });
+        let args = fs::read_to_string(&output).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        assert!(args.windows(2).any(|w| w == ["-map_metadata", "0"]), "{:?}", args);
+        for tag in ["creation_time=2023-07-14T09:30:12.000000Z", "title=Summer holiday", "artist=Alex", "author=Alex"] {
+            assert!(args.windows(2).any(|w| w == ["-metadata", tag]), "missing {}: {:?}", tag, args);
+        }
+        assert!(!args.iter().any(|arg| arg.starts_with("comment=")), "{:?}", args);
+        assert_eq!(modified(&output), UNIX_EPOCH + Duration::from_secs(RECORDED_SECS));
+    }
+
+    #[test]
+    fn test_untagged_input_keeps_its_modification_time() {
+        tools();
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "untagged.mts");
+        let recorded = UNIX_EPOCH + Duration::from_secs(RECORDED_SECS - 3600);
+        fs::File::options().write(true).open(&input).unwrap().set_modified(recorded).unwrap();
+
+        let output = convert(&input, &ConversionOptions::default());
+        assert_eq!(modified(&output), recorded);
+    }
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use magic_converter::video::OutputTemplate;
+use std::path::{Path, PathBuf};
+
+const VIDEO_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080 },
+    { "index": 1, "codec_type": "audio", "codec_name": "ac3" }
+], "format": { "format_name": "mpegts", "duration": "10.0" } }"#;
+
+fn template(s: &str) -> OutputTemplate {
+    s.parse().unwrap()
+}
+
+#[test]
+fn test_invalid_templates_are_rejected() {
+    let invalid = ["", "{stem", "stem}", "{name}", "{date:%B}", "{index:x}", "../{stem}", "/videos/{stem}"];
+    for invalid in invalid {
+        assert!(invalid.parse::<OutputTemplate>().is_err(), "{:?} was accepted", invalid);
+    }
+    assert_eq!(template("{date:%Y}/{stem}").to_string(), "{date:%Y}/{stem}");
+    assert_eq!(OutputTemplate::default().to_string(), "{stem}");
+}
+
+#[test]
+fn test_names_from_the_file_name() {
+    let input = Path::new("card/00001.MTS");
+    assert_eq!(template("{stem}_{ext}").render(input, 7, "mp4"), PathBuf::from("00001_MTS.mp4"));
+    assert_eq!(template("clip-{index:3}").render(input, 7, "mkv"), PathBuf::from("clip-007.mkv"));
+    assert_eq!(template("{ext}/{index}-{stem}").render(input, 12, "mp4"), PathBuf::from("MTS/12-00001.mp4"));
+    assert_eq!(template("archive/").render(input, 1, "mp4"), PathBuf::from("archive/00001.mp4"));
+    assert_eq!(template("a:b {stem}").render(input, 1, "mp4"), PathBuf::from("a:b 00001.mp4"), "literals are kept");
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, dir_entries, stand_in_tools_with_probe};
+    use super::{template, VIDEO_PROBE};
+    use magic_converter::video::{
+        convert_mts_files_in_directory_with_options, convert_mts_to_mp4_with_options, BatchOptions, Conversion,
+        ConversionOptions, FileOutcome, OverwritePolicy,
+    };
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::tempdir;
+
+    // 2023-07-14 09:30:12 UTC
+    fn recorded_input(dir: &Path, name: &str) -> PathBuf {
+        let input = create_input(dir, name);
+        let recorded = UNIX_EPOCH + Duration::from_secs(1_689_327_012);
+        fs::File::options().write(true).open(&input).unwrap().set_modified(recorded).unwrap();
+        input
+    }
+
+    #[test]
+    fn test_names_from_the_recording() {
+        stand_in_tools_with_probe(VIDEO_PROBE);
+        let dir = tempdir().unwrap();
+        let input = recorded_input(dir.path(), "clip.mts");
+
+        let name = template("{date:%Y}/{date:%m}/{date}_{time}_{width}x{height}").render(&input, 1, "mp4");
+        assert_eq!(name, PathBuf::from("2023/07/2023-07-14_09-30-12_1920x1080.mp4"));
+        let camera = template("{camera} {maker}-{model_code}").render(&input, 1, "mp4");
+        assert_eq!(camera, PathBuf::from("unknown unknown-unknown.mp4"));
+    }
+
+    #[test]
+    fn test_single_file_goes_into_subdirectories() {
+        stand_in_tools_with_probe(VIDEO_PROBE);
+        let dir = tempdir().unwrap();
+        let input = recorded_input(dir.path(), "clip.mts");
+        let output_dir = tempdir().unwrap();
+        let options =
+            ConversionOptions { output_name: template("{date:%Y}/{date:%m}/"), ..ConversionOptions::default() };
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(output_dir.path()), &options, None);
+        let Ok(Conversion::Converted { output, .. }) = result else {
+            panic!("Expected a conversion, got {:?}", result);
+        };
+        assert_eq!(output, output_dir.path().join("2023").join("07").join("clip.mp4"));
+        assert!(output.is_file());
+    }
+
+    #[test]
+    fn test_colliding_names_in_a_batch_are_numbered() {
+        stand_in_tools_with_probe(VIDEO_PROBE);
+        let dir = tempdir().unwrap();
+        create_input(dir.path(), "clip.MTS");
+        create_input(dir.path(), "clip.mov");
+
+        let report =
+            convert_mts_files_in_directory_with_options(dir.path(), None, &BatchOptions::default(), None).unwrap();
+        assert_eq!(report.converted(), 2);
+        assert_eq!(
+            dir_entries(dir.path()),
+            [".magic-converter-journal.json", "clip.MTS", "clip.mov", "clip.mp4", "clip_1.mp4"]
+        );
+    }
+
+    #[test]
+    fn test_renamed_outputs_do_not_race() {
+        stand_in_tools_with_probe(VIDEO_PROBE);
+        let input_dir = tempdir().unwrap();
+        for name in ["a.mts", "b.mts", "c.mts", "d.mts"] {
+            create_input(input_dir.path(), name);
+        }
+        let output_dir = tempdir().unwrap();
+        fs::write(output_dir.path().join("same.mp4"), "existing").unwrap();
+        let conversion = ConversionOptions {
+            output_name: template("same"),
+            overwrite: OverwritePolicy::Rename,
+            ..ConversionOptions::default()
+        };
+        let options = BatchOptions { conversion, workers: 4, ..BatchOptions::default() };
+
+        let report =
+            convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None)
+                .unwrap();
+        let outputs: Vec<_> = report
+            .entries
+            .iter()
+            .map(|entry| match &entry.outcome {
+                FileOutcome::Converted { output, .. } => output.file_name().unwrap().to_owned(),
+                outcome => panic!("{} was not converted: {:?}", entry.input.display(), outcome),
+            })
+            .collect();
+        assert_eq!(outputs, ["same_1.mp4", "same_2.mp4", "same_3.mp4", "same_4.mp4"]);
+        assert_eq!(fs::read_to_string(output_dir.path().join("same.mp4")).unwrap(), "existing");
+    }
+}
@@ -0,0 +1,97 @@
+// Runs the converter against a stand-in ffmpeg script, so these tests need a POSIX shell.
+#![cfg(unix)]
+
+mod common;
+
+use common::{create_input, dir_entries, stand_in_tools_with_probes};
+use magic_converter::video::{
+    convert_mts_to_mp4_with_options, Conversion, ConversionOptions, ConvertError, OverwritePolicy,
+};
+use std::fs;
+use tempfile::tempdir;
+
+const CLIP_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "aac" }
+], "format": { "format_name": "mpegts", "duration": "10.0" } }"#;
+const NO_DURATION_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "aac" }
+], "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2" } }"#;
+const AUDIO_ONLY_PROBE: &str = r#"{ "streams": [{ "index": 0, "codec_type": "audio", "codec_name": "aac" }],
+    "format": { "format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "10.0" } }"#;
+
+// Outputs are still `.part` files when they are checked; `truncated` and `lost_video` inputs come out broken
+fn stand_in_tools() {
+    stand_in_tools_with_probes(&[
+        ("*truncated*.part", Some(NO_DURATION_PROBE)),
+        ("*lost_video*.part", Some(AUDIO_ONLY_PROBE)),
+        ("*", Some(CLIP_PROBE)),
+    ]);
+}
+
+#[test]
+fn test_successful_conversion_leaves_no_temporary_file() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "clip.mts");
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &ConversionOptions::default(), None);
+    let expected = dir.path().join("clip.mp4");
+    assert!(matches!(result, Ok(Conversion::Converted { ref output, .. }) if *output == expected), "got {:?}", result);
+    assert_eq!(dir_entries(dir.path()), ["clip.mp4", "clip.mts"]);
+}
+
+#[test]
+fn test_failed_conversion_leaves_no_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "broken.mts");
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &ConversionOptions::default(), None);
+    assert!(matches!(result, Err(ConvertError::FfmpegFailed { .. })), "got {:?}", result);
+    assert_eq!(dir_entries(dir.path()), ["broken.mts"]);
+}
+
+#[test]
+fn test_failed_overwrite_keeps_previous_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "broken.mts");
+    let output = dir.path().join("broken.mp4");
+    fs::write(&output, "old").unwrap();
+
+    let options = ConversionOptions { overwrite: OverwritePolicy::Overwrite, ..ConversionOptions::default() };
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+    assert!(result.is_err());
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+    assert_eq!(dir_entries(dir.path()), ["broken.mp4", "broken.mts"]);
+}
+
+#[test]
+fn test_broken_output_never_replaces_the_previous_one() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let options = ConversionOptions { overwrite: OverwritePolicy::Overwrite, ..ConversionOptions::default() };
+    for name in ["truncated", "lost_video"] {
+        let input = create_input(dir.path(), &format!("{}.mts", name));
+        let output = dir.path().join(format!("{}.mp4", name));
+        fs::write(&output, "old").unwrap();
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+        assert!(matches!(result, Err(ConvertError::InvalidOutput { .. })), "got {:?}", result);
+        assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+    }
+    assert_eq!(dir_entries(dir.path()), ["lost_video.mp4", "lost_video.mts", "truncated.mp4", "truncated.mts"]);
+}
+
+#[test]
+fn test_empty_output_is_rejected() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "empty.mts");
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &ConversionOptions::default(), None);
+    assert!(matches!(result, Err(ConvertError::EmptyOutput(_))), "got {:?}", result);
+    assert_eq!(dir_entries(dir.path()), ["empty.mts"]);
+}
@@ -0,0 +1,117 @@
+// Runs the converter against a stand-in ffmpeg script, so these tests need a POSIX shell.
+#![cfg(unix)]
+
+mod common;
+
+use common::{create_input, stand_in_tools};
+use magic_converter::video::{
+    convert_mts_files_in_directory_with_options, convert_mts_to_mp4_with_options, BatchOptions, Conversion,
+    ConversionOptions, ConvertError, FileOutcome, OverwritePolicy,
+};
+use std::fs;
+use tempfile::tempdir;
+
+fn options(overwrite: OverwritePolicy) -> ConversionOptions {
+    ConversionOptions { overwrite, ..ConversionOptions::default() }
+}
+
+#[test]
+fn test_resolve_free_path_for_every_policy() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("clip.mp4");
+    for policy in OverwritePolicy::ALL {
+        assert_eq!(policy.resolve(&path).unwrap(), Some(path.clone()), "{}", policy);
+    }
+}
+
+#[test]
+fn test_resolve_rename_picks_first_free_suffix() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("clip.mp4");
+    fs::write(&path, "old").unwrap();
+    fs::write(dir.path().join("clip_1.mp4"), "old").unwrap();
+
+    let renamed = OverwritePolicy::Rename.resolve(&path).unwrap();
+    assert_eq!(renamed, Some(dir.path().join("clip_2.mp4")));
+}
+
+#[test]
+fn test_policy_names_round_trip() {
+    for policy in OverwritePolicy::ALL {
+        assert_eq!(policy.to_string().parse::<OverwritePolicy>(), Ok(policy));
+    }
+    assert_eq!("SKIP".parse::<OverwritePolicy>(), Ok(OverwritePolicy::Skip));
+    assert!("replace".parse::<OverwritePolicy>().is_err());
+}
+
+#[test]
+fn test_error_policy_keeps_existing_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "clip.mts");
+    let output = dir.path().join("clip.mp4");
+    fs::write(&output, "old").unwrap();
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options(OverwritePolicy::Error), None);
+    assert!(matches!(result, Err(ConvertError::OutputExists(ref path)) if *path == output), "got {:?}", result);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+}
+
+#[test]
+fn test_skip_policy_leaves_existing_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "clip.mts");
+    let output = dir.path().join("clip.mp4");
+    fs::write(&output, "old").unwrap();
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options(OverwritePolicy::Skip), None);
+    assert_eq!(result.unwrap(), Conversion::Skipped(output.clone()));
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+}
+
+#[test]
+fn test_overwrite_policy_replaces_existing_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "clip.mts");
+    let output = dir.path().join("clip.mp4");
+    fs::write(&output, "old").unwrap();
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options(OverwritePolicy::Overwrite), None);
+    assert!(matches!(result, Ok(Conversion::Converted { output: ref path, .. }) if *path == output), "got {:?}", result);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "mp4");
+}
+
+#[test]
+fn test_rename_policy_writes_next_to_existing_output() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "clip.mts");
+    let output = dir.path().join("clip.mp4");
+    fs::write(&output, "old").unwrap();
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options(OverwritePolicy::Rename), None);
+    let renamed = dir.path().join("clip_1.mp4");
+    assert!(matches!(result, Ok(Conversion::Converted { output: ref path, .. }) if *path == renamed), "got {:?}", result);
+    assert_eq!(fs::read_to_string(&output).unwrap(), "old");
+    assert_eq!(fs::read_to_string(&renamed).unwrap(), "mp4");
+}
+
+#[test]
+fn test_batch_reports_skipped_outputs() {
+    stand_in_tools();
+    let input_dir = tempdir().unwrap();
+    let output_dir = tempdir().unwrap();
+    create_input(input_dir.path(), "a.mts");
+    create_input(input_dir.path(), "b.mts");
+    fs::write(output_dir.path().join("a.mp4"), "old").unwrap();
+
+    let options = BatchOptions { conversion: options(OverwritePolicy::Skip), ..BatchOptions::default() };
+    let report =
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None).unwrap();
+
+    assert_eq!((report.converted(), report.skipped(), report.failed()), (1, 1, 0));
+    assert!(matches!(report.entries[0].outcome, FileOutcome::Skipped { .. }));
+    assert_eq!(fs::read_to_string(output_dir.path().join("a.mp4")).unwrap(), "old");
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use magic_converter::video::{
+    AudioCodec, AudioSettings, ConversionPlan, EncodingSettings, MediaInfo, OutputFormat, StreamAction, StreamMode,
+};
+use std::path::Path;
+
+fn media(streams: &[(&str, &str)]) -> MediaInfo {
+    let streams: Vec<String> = streams
+        .iter()
+        .enumerate()
+        .map(|(index, (kind, codec))| {
+            format!(r#"{{ "index": {}, "codec_type": "{}", "codec_name": "{}" }}"#, index, kind, codec)
+        })
+        .collect();
+    let json = format!(r#"{{ "streams": [{}], "format": {{ "format_name": "mpegts" }} }}"#, streams.join(","));
+    MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), &json).unwrap()
+}
+
+fn actions(plan: &ConversionPlan) -> Vec<StreamAction> {
+    plan.streams.iter().map(|stream| stream.action.clone()).collect()
+}
+
+fn transcode(encoder: &str) -> StreamAction {
+    StreamAction::Transcode { encoder: encoder.to_string() }
+}
+
+#[test]
+fn test_auto_copies_compatible_streams() {
+    let plan = ConversionPlan::for_mp4(&media(&[("video", "h264"), ("audio", "aac")]), StreamMode::Auto);
+    assert_eq!(actions(&plan), [StreamAction::Copy, StreamAction::Copy]);
+    assert!(plan.is_remux());
+}
+
+#[test]
+fn test_auto_transcodes_avchd_audio_and_drops_pgs() {
+    let info = media(&[("video", "h264"), ("audio", "ac3"), ("subtitle", "hdmv_pgs_subtitle")]);
+    let plan = ConversionPlan::for_mp4(&info, StreamMode::Auto);
+    assert_eq!(actions(&plan), [StreamAction::Copy, transcode("aac"), StreamAction::Drop]);
+    assert!(!plan.is_remux());
+    assert_eq!(plan.kept().count(), 2);
+    assert_eq!(plan.to_string(), "#0 h264 copy, #1 ac3 -> aac, #2 hdmv_pgs_subtitle dropped");
+}
+
+#[test]
+fn test_auto_transcodes_mpeg2_and_lpcm() {
+    let plan = ConversionPlan::for_mp4(&media(&[("video", "mpeg2video"), ("audio", "pcm_bluray")]), StreamMode::Auto);
+    assert_eq!(actions(&plan), [transcode("libx264"), transcode("aac")]);
+}
+
+#[test]
+fn test_text_subtitles_become_mov_text() {
+    let plan = ConversionPlan::for_mp4(&media(&[("subtitle", "subrip"), ("data", "bin_data")]), StreamMode::Auto);
+    assert_eq!(actions(&plan), [transcode("mov_text"), StreamAction::Drop]);
+}
+
+#[test]
+fn test_mode_overrides() {
+    let info = media(&[("video", "h264"), ("audio", "ac3"), ("subtitle", "hdmv_pgs_subtitle")]);
+    let copy = ConversionPlan::for_mp4(&info, StreamMode::Copy);
+    assert_eq!(actions(&copy), [StreamAction::Copy, StreamAction::Copy, StreamAction::Drop]);
+
+    let transcoded = ConversionPlan::for_mp4(&info, StreamMode::Transcode);
+    assert_eq!(actions(&transcoded), [transcode("libx264"), transcode("aac"), StreamAction::Drop]);
+}
+
+#[test]
+fn test_bitstream_filters() {
+    let plan = ConversionPlan::for_mp4(&media(&[("video", "h264"), ("audio", "aac")]), StreamMode::Auto);
+    let filters: Vec<_> = plan.streams.iter().map(|stream| stream.bitstream_filter()).collect();
+    assert_eq!(filters, [None, Some("aac_adtstoasc")]);
+
+    let transcoded = ConversionPlan::for_mp4(&media(&[("audio", "aac")]), StreamMode::Transcode);
+    assert_eq!(transcoded.streams[0].bitstream_filter(), None, "the encoder already writes raw AAC");
+}
+
+#[test]
+fn test_codec_override_keeps_dropped_streams_dropped() {
+    let info = media(&[("video", "h264"), ("audio", "ac3"), ("audio", "aac")]);
+    let encoding = EncodingSettings {
+        audio: Some(AudioSettings { codec: AudioCodec::Mp3, bitrate_kbps: None }),
+        ..EncodingSettings::default()
+    };
+    let plan = ConversionPlan::for_format(&info, OutputFormat::Mp3, StreamMode::Auto).with_encoding(&encoding);
+    assert_eq!(actions(&plan), [StreamAction::Drop, transcode("libmp3lame"), StreamAction::Drop]);
+}
+
+#[test]
+fn test_stream_mode_names_round_trip() {
+    for mode in StreamMode::ALL {
+        assert_eq!(mode.to_string().parse::<StreamMode>(), Ok(mode));
+    }
+    assert!("remux".parse::<StreamMode>().is_err());
+}
+
+#[test]
+fn test_unprobed_plan_copies_default_streams() {
+    let plan = ConversionPlan::default();
+    assert!(plan.is_remux());
+    assert_eq!(plan.to_string(), "copy default streams");
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so this needs a POSIX shell
+#[cfg(unix)]
+#[test]
+fn test_conversion_maps_planned_streams() {
+    use magic_converter::video::{convert_mts_to_mp4_with_options, Conversion, ConversionOptions};
+    use std::fs;
+
+    common::stand_in_tools_with_probe(
+        r#"{ "streams": [
+            { "index": 0, "codec_type": "video", "codec_name": "h264" },
+            { "index": 1, "codec_type": "audio", "codec_name": "ac3" },
+            { "index": 2, "codec_type": "subtitle", "codec_name": "hdmv_pgs_subtitle" },
+            { "index": 3, "codec_type": "audio", "codec_name": "aac" }
+        ], "format": { "format_name": "mpegts", "duration": "10.0" } }"#,
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let input = common::create_input(dir.path(), "args.mts");
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &ConversionOptions::default(), None);
+    let Ok(Conversion::Converted { output, plan }) = result else {
+        panic!("Expected a conversion, got {:?}", result);
+    };
+    assert_eq!(actions(&plan), [StreamAction::Copy, transcode("aac"), StreamAction::Drop, StreamAction::Copy]);
+
+    let args = fs::read_to_string(output).unwrap();
+    let args: Vec<&str> = args.lines().collect();
+    let position = |arg: &str| args.iter().position(|a| *a == arg);
+    assert!(args.windows(4).any(|w| w == ["-map", "0:0", "-c:0", "copy"]), "{:?}", args);
+    assert!(args.windows(4).any(|w| w == ["-map", "0:1", "-c:1", "aac"]), "{:?}", args);
+    assert!(args.windows(6).any(|w| w == ["-map", "0:3", "-c:2", "copy", "-bsf:2", "aac_adtstoasc"]), "{:?}", args);
+    assert_eq!(position("0:2"), None, "PGS subtitles should not be mapped");
+    assert_eq!(position("h264_mp4toannexb"), None, "MP4 stores H.264 as AVC, not Annex B");
+}
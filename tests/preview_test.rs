@@ -0,0 +1,97 @@
+mod common;
+
+use magic_converter::video::AnimationFormat;
+
+const CLIP_PROBE: &str = r#"{ "streams": [{ "index": 0, "codec_type": "video", "codec_name": "h264" }],
+    "format": { "format_name": "mpegts", "duration": "100.0" } }"#;
+
+#[test]
+fn test_parse_animation_format() {
+    assert_eq!("GIF".parse::<AnimationFormat>(), Ok(AnimationFormat::Gif));
+    assert_eq!("webp".parse::<AnimationFormat>(), Ok(AnimationFormat::Webp));
+    assert!("apng".parse::<AnimationFormat>().is_err());
+}
+
+// Runs against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, dir_entries, stand_in_tools_with_probe};
+    use super::CLIP_PROBE;
+    use magic_converter::video::{
+        create_preview, AnimationFormat, ConversionOptions, ConversionPhase, OverwritePolicy, PreviewOptions,
+        ProgressEvent, ProgressEventCallback, ThumbnailTime,
+    };
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn args(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap().lines().map(String::from).collect()
+    }
+
+    fn has(args: &[String], expected: &[&str]) -> bool {
+        args.windows(expected.len()).any(|window| window == expected)
+    }
+
+    #[test]
+    fn test_gif_preview() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let phases = Arc::new(Mutex::new(Vec::new()));
+        let seen = phases.clone();
+        let callback: ProgressEventCallback =
+            Arc::new(move |event: &ProgressEvent| seen.lock().unwrap().push(event.phase));
+
+        let preview =
+            create_preview(&input, None, &PreviewOptions::default(), &ConversionOptions::default(), Some(callback))
+                .unwrap()
+                .unwrap();
+        assert_eq!(preview, dir.path().join("args_preview.gif"));
+        let preview_args = args(&preview);
+        assert!(has(&preview_args, &["-ss", "10.000", "-t", "3.000", "-i"]), "{:?}", preview_args);
+        let graph = preview_args.iter().find(|arg| arg.contains("palettegen")).expect("a palette filter graph");
+        assert!(graph.contains("fps=12,scale=480:-1:flags=lanczos"), "{}", graph);
+        assert!(graph.contains("paletteuse"), "{}", graph);
+        assert!(has(&preview_args, &["-f", "gif"]), "{:?}", preview_args);
+        assert!(has(&preview_args, &["-loop", "0"]), "{:?}", preview_args);
+        assert_eq!(phases.lock().unwrap().last(), Some(&ConversionPhase::Finished));
+        assert_eq!(dir_entries(dir.path()), ["args.mts", "args_preview.gif"]);
+    }
+
+    #[test]
+    fn test_webp_preview_stops_at_the_end_of_the_clip() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let options = PreviewOptions {
+            format: AnimationFormat::Webp,
+            start: ThumbnailTime::At(Duration::from_secs(99)),
+            width: 320,
+            ..PreviewOptions::default()
+        };
+
+        let preview = create_preview(&input, None, &options, &ConversionOptions::default(), None).unwrap().unwrap();
+        assert_eq!(preview.extension().unwrap(), "webp");
+        let preview_args = args(&preview);
+        assert!(has(&preview_args, &["-ss", "99.000", "-t", "1.000"]), "{:?}", preview_args);
+        assert!(has(&preview_args, &["-filter:v", "fps=12,scale=320:-1:flags=lanczos"]), "{:?}", preview_args);
+        assert!(has(&preview_args, &["-c:v", "libwebp"]), "{:?}", preview_args);
+        assert!(has(&preview_args, &["-f", "webp"]), "{:?}", preview_args);
+    }
+
+    #[test]
+    fn test_existing_preview_is_skipped() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "clip.mts");
+        fs::write(dir.path().join("clip_preview.gif"), "keep").unwrap();
+        let options = ConversionOptions { overwrite: OverwritePolicy::Skip, ..ConversionOptions::default() };
+
+        let preview = create_preview(&input, None, &PreviewOptions::default(), &options, None).unwrap();
+        assert_eq!(preview, None);
+        assert_eq!(fs::read_to_string(dir.path().join("clip_preview.gif")).unwrap(), "keep");
+    }
+}
@@ -0,0 +1,122 @@
+use magic_converter::video::{probe, ConvertError, MediaInfo, StreamKind};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::tempdir;
+
+// Trimmed `ffprobe -of json -show_format -show_streams` output for an AVCHD clip
+const AVCHD_PROBE: &str = r#"{
+    "streams": [
+        {
+            "index": 1,
+            "codec_name": "ac3",
+            "codec_long_name": "ATSC A/52A (AC-3)",
+            "codec_type": "audio",
+            "sample_rate": "48000",
+            "channels": 2,
+            "channel_layout": "stereo",
+            "r_frame_rate": "0/0",
+            "avg_frame_rate": "0/0",
+            "bit_rate": "256000",
+            "tags": { "language": "eng" }
+        },
+        {
+            "index": 0,
+            "codec_name": "h264",
+            "profile": "High",
+            "codec_type": "video",
+            "width": 1920,
+            "height": 1080,
+            "pix_fmt": "yuv420p",
+            "r_frame_rate": "30000/1001",
+            "avg_frame_rate": "0/0"
+        },
+        {
+            "index": 2,
+            "codec_name": "hdmv_pgs_subtitle",
+            "codec_type": "subtitle",
+            "tags": { "language": "und" }
+        }
+    ],
+    "format": {
+        "filename": "00001.MTS",
+        "format_name": "mpegts",
+        "format_long_name": "MPEG-TS (MPEG-2 Transport Stream)",
+        "duration": "10.010000",
+        "start_time": "1.400000",
+        "size": "30408704",
+        "bit_rate": "24302560",
+        "tags": { "creation_time": "2023-07-14T09:30:12.000000Z" }
+    }
+}"#;
+
+#[test]
+fn test_parse_ffprobe_json() {
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap();
+
+    assert_eq!(info.container, "mpegts");
+    assert_eq!(info.duration, Some(Duration::from_millis(10010)));
+    assert_eq!(info.start_time, Some(Duration::from_millis(1400)));
+    assert_eq!(info.size, Some(30408704));
+    assert_eq!(info.bit_rate, Some(24302560));
+    assert_eq!(info.creation_time.as_deref(), Some("2023-07-14T09:30:12.000000Z"));
+    assert_eq!(info.streams.iter().map(|s| s.index).collect::<Vec<_>>(), [0, 1, 2]);
+
+    let video = info.video_stream().unwrap();
+    assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+    assert_eq!(video.codec.as_deref(), Some("h264"));
+    assert_eq!(video.pixel_format.as_deref(), Some("yuv420p"));
+    assert!((video.frame_rate.unwrap() - 29.97).abs() < 0.01, "falls back to r_frame_rate");
+
+    let audio: Vec<_> = info.audio_streams().collect();
+    assert_eq!(audio.len(), 1);
+    assert_eq!(audio[0].channels, Some(2));
+    assert_eq!(audio[0].sample_rate, Some(48000));
+    assert_eq!(audio[0].language.as_deref(), Some("eng"));
+    assert_eq!(audio[0].frame_rate, None);
+
+    assert_eq!(info.streams[2].kind, StreamKind::Subtitle);
+    assert_eq!(info.streams[2].language, None, "`und` means no language");
+}
+
+#[test]
+fn test_progress_totals_from_media_info() {
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap();
+    let totals = info.progress_totals();
+    assert_eq!(totals.duration, Some(Duration::from_millis(10010)));
+    assert_eq!(totals.frames, Some(300), "Frame count should be derived from duration and frame rate");
+
+    let live = r#"{ "streams": [{ "index": 0, "codec_type": "video", "avg_frame_rate": "0/0" }],
+        "format": { "format_name": "mpegts", "duration": "N/A" } }"#;
+    let totals = MediaInfo::from_ffprobe_json(Path::new("live.ts"), live).unwrap().progress_totals();
+    assert_eq!((totals.duration, totals.frames), (None, None));
+}
+
+#[test]
+fn test_media_info_serializes_duration_as_seconds() {
+    let info = MediaInfo::from_ffprobe_json(Path::new("00001.MTS"), AVCHD_PROBE).unwrap();
+    let json: serde_json::Value = serde_json::to_value(&info).unwrap();
+    assert_eq!(json["duration"], 10.01);
+    assert_eq!(json["streams"][0]["kind"], "video");
+}
+
+#[test]
+fn test_invalid_ffprobe_json() {
+    assert!(MediaInfo::from_ffprobe_json(Path::new("x.mts"), "not json").is_err());
+}
+
+#[test]
+fn test_probe_missing_input() {
+    let dir = tempdir().unwrap();
+    let missing = dir.path().join("missing.mts");
+    assert!(matches!(probe(&missing), Err(ConvertError::InputNotFound(path)) if path == missing));
+}
+
+#[test]
+fn test_probe_without_ffprobe() {
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("clip.mts");
+    std::fs::write(&input, b"HDMV").unwrap();
+    std::env::set_var("MAGIC_CONVERTER_FFPROBE", dir.path().join("missing-ffprobe"));
+
+    assert!(matches!(probe(&input), Err(ConvertError::FfprobeNotFound)));
+}
@@ -0,0 +1,117 @@
+use magic_converter::video::progress::{format_duration, ProgressTotals, ProgressTracker};
+use magic_converter::video::{progress_adapter, ConversionPhase, ProgressEvent};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn totals(duration_secs: Option<u64>, frames: Option<u64>, bytes: Option<u64>) -> ProgressTotals {
+    ProgressTotals {
+        duration: duration_secs.map(Duration::from_secs),
+        frames,
+        bytes,
+    }
+}
+
+#[test]
+fn test_progress_from_out_time() {
+    let mut tracker = ProgressTracker::new(totals(Some(10), Some(250), None));
+    assert_eq!(tracker.update("out_time_us", "2500000"), Some(0.25));
+    assert_eq!(tracker.update("frame", "200"), None, "Frames are ignored when the duration is known");
+    assert_eq!(tracker.update("out_time_us", "5000000"), Some(0.5));
+}
+
+#[test]
+fn test_progress_falls_back_to_frames_then_size() {
+    let mut by_frames = ProgressTracker::new(totals(None, Some(200), Some(1000)));
+    assert_eq!(by_frames.update("frame", "50"), Some(0.25));
+    assert_eq!(by_frames.update("total_size", "900"), None);
+
+    let mut by_size = ProgressTracker::new(totals(None, None, Some(1000)));
+    assert_eq!(by_size.update("out_time_us", "N/A"), None);
+    assert_eq!(by_size.update("total_size", "400"), Some(0.4));
+}
+
+#[test]
+fn test_progress_is_clamped_and_monotonic() {
+    let mut tracker = ProgressTracker::new(totals(Some(4), None, None));
+    let updates = ["1000000", "3000000", "2000000", "-5", "9000000", "N/A"];
+    let mut reported = Vec::new();
+    for value in updates {
+        if let Some(progress) = tracker.update("out_time_us", value) {
+            reported.push(progress);
+        }
+    }
+
+    assert_eq!(reported, vec![0.25, 0.75, 1.0]);
+    assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(tracker.finish(), None, "Completion should not be reported twice");
+}
+
+#[test]
+fn test_progress_finish_without_totals() {
+    let mut tracker = ProgressTracker::new(ProgressTotals::default());
+    assert_eq!(tracker.update("frame", "120"), None);
+    assert_eq!(tracker.update("total_size", "4096"), None);
+    assert_eq!(tracker.finish(), Some(1.0));
+    assert_eq!(tracker.current(), 1.0);
+}
+
+#[test]
+fn test_progress_stats_from_ffmpeg_block() {
+    let block = "frame=240\nfps=59.94\nbitrate=16523.4kbits/s\ntotal_size=1048576\n\
+                 out_time_us=4004000\nout_time=00:00:04.004000\nspeed=2.01x\nprogress=continue\n";
+    let mut tracker = ProgressTracker::new(totals(Some(8), None, None));
+    for line in block.lines() {
+        let (key, value) = line.trim().split_once('=').unwrap();
+        tracker.update(key, value);
+    }
+
+    let stats = tracker.stats();
+    assert_eq!(stats.frame, Some(240));
+    assert_eq!(stats.fps, Some(59.94));
+    assert_eq!(stats.bitrate_kbps, Some(16523.4));
+    assert_eq!(stats.bytes_written, Some(1048576));
+    assert_eq!(stats.out_time, Some(Duration::from_micros(4004000)));
+    assert_eq!(stats.speed, Some(2.01));
+    assert!((tracker.current() - 0.5005).abs() < 1e-4);
+
+    tracker.update("bitrate", "N/A");
+    tracker.update("speed", "N/A");
+    assert_eq!(tracker.stats().bitrate_kbps, None);
+    assert_eq!(tracker.stats().speed, None);
+}
+
+#[test]
+fn test_progress_adapter_forwards_overall_progress() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&received);
+    let adapter = progress_adapter(Arc::new(move |progress: f32| sink.lock().unwrap().push(progress)));
+
+    let mut event = ProgressEvent {
+        file: PathBuf::from("00001.MTS"),
+        file_index: 1,
+        file_count: 4,
+        phase: ConversionPhase::Converting,
+        progress: 0.5,
+        overall_progress: 0.375,
+        frame: None,
+        fps: None,
+        bitrate_kbps: None,
+        out_time: None,
+        speed: Some(3.0),
+        eta: Some(Duration::from_secs(65)),
+        bytes_written: None,
+    };
+    adapter(&event);
+    event.phase = ConversionPhase::Finished;
+    event.overall_progress = 0.5;
+    adapter(&event);
+
+    assert_eq!(*received.lock().unwrap(), vec![0.375, 0.5]);
+}
+
+#[test]
+fn test_format_duration() {
+    assert_eq!(format_duration(Duration::from_secs(65)), "00:01:05");
+    assert_eq!(format_duration(Duration::from_secs(3 * 3600 + 7)), "03:00:07");
+}
@@ -0,0 +1,66 @@
+use magic_converter::video::{convert_mts_to_mp4_with_options, probe, Conversion, ConversionOptions, StreamAction};
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn tools_available() -> bool {
+    ["ffmpeg", "ffprobe"].iter().all(|tool| Command::new(tool).arg("-version").output().is_ok())
+}
+
+// Two seconds of H.264/AAC in an MPEG transport stream, like a camera writes
+fn generate_transport_stream(path: &Path) -> bool {
+    Command::new("ffmpeg")
+        .args(["-v", "error", "-y"])
+        .args(["-f", "lavfi", "-i", "testsrc=duration=2:size=320x240:rate=25"])
+        .args(["-f", "lavfi", "-i", "sine=frequency=440:duration=2"])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p", "-c:a", "aac", "-f", "mpegts"])
+        .arg(path)
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+// Integration test that only runs if ffmpeg and ffprobe (with libx264) are available
+#[test]
+fn test_remux_h264_aac_transport_stream() {
+    if !tools_available() {
+        println!("Skipping remux test - ffmpeg not available");
+        return;
+    }
+    let dir = tempdir().unwrap();
+    let input = dir.path().join("00001.MTS");
+    if !generate_transport_stream(&input) {
+        println!("Skipping remux test - could not generate an H.264/AAC input");
+        return;
+    }
+
+    let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &ConversionOptions::default(), None);
+    let Ok(Conversion::Converted { output, plan }) = result else {
+        panic!("Expected a conversion, got {:?}", result);
+    };
+    assert!(plan.streams.iter().all(|stream| stream.action == StreamAction::Copy), "{}", plan);
+
+    let info = probe(&output).unwrap();
+    assert!(info.container.contains("mp4"), "container: {}", info.container);
+    let codecs: Vec<_> = info.streams.iter().map(|stream| stream.codec.as_deref().unwrap_or_default()).collect();
+    assert_eq!(codecs, ["h264", "aac"]);
+    let duration = info.duration.unwrap().as_secs_f64();
+    assert!((duration - 2.0).abs() < 0.2, "duration: {}", duration);
+
+    // MP4 stores H.264 length-prefixed (AVC), never in Annex B framing
+    let is_avc = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0", "-show_entries", "stream=is_avc", "-of", "csv=p=0"])
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&is_avc.stdout).trim(), "true");
+
+    // Decoding the whole file catches broken AAC framing and bitstreams
+    let decode = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(&output)
+        .args(["-f", "null", "-"])
+        .output()
+        .unwrap();
+    assert!(decode.status.success());
+    assert!(decode.stderr.is_empty(), "decode errors: {}", String::from_utf8_lossy(&decode.stderr));
+}
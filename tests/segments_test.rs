@@ -0,0 +1,140 @@
+mod common;
+
+use magic_converter::video::segments::is_continuation;
+use magic_converter::video::MediaInfo;
+use std::path::Path;
+
+// The first two files of a recording split at the 2 GB limit, then a new recording
+const FIRST_SEGMENT_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080 },
+    { "index": 1, "codec_type": "audio", "codec_name": "ac3" }
+], "format": { "format_name": "mpegts", "start_time": "1.400000", "duration": "1000.000000" } }"#;
+const SECOND_SEGMENT_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080 },
+    { "index": 1, "codec_type": "audio", "codec_name": "ac3" }
+], "format": { "format_name": "mpegts", "start_time": "1001.433000", "duration": "200.000000" } }"#;
+const NEW_RECORDING_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080 },
+    { "index": 1, "codec_type": "audio", "codec_name": "ac3" }
+], "format": { "format_name": "mpegts", "start_time": "1.400000", "duration": "30.000000" } }"#;
+
+fn info(name: &str, json: &str) -> MediaInfo {
+    MediaInfo::from_ffprobe_json(Path::new(name), json).unwrap()
+}
+
+#[test]
+fn test_continuation_follows_on_in_time() {
+    let first = info("00000.MTS", FIRST_SEGMENT_PROBE);
+    let second = info("00001.MTS", SECOND_SEGMENT_PROBE);
+    let new = info("00002.MTS", NEW_RECORDING_PROBE);
+    assert!(is_continuation(&first, &second));
+    assert!(!is_continuation(&second, &new), "a new recording restarts the clock");
+    assert!(!is_continuation(&second, &first));
+}
+
+#[test]
+fn test_continuation_needs_the_same_streams() {
+    let first = info("00000.MTS", FIRST_SEGMENT_PROBE);
+    let other = SECOND_SEGMENT_PROBE.replace("1080", "720");
+    assert!(!is_continuation(&first, &info("00001.MTS", &other)));
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, dir_entries, stand_in_tools_with_probes};
+    use super::{FIRST_SEGMENT_PROBE, NEW_RECORDING_PROBE, SECOND_SEGMENT_PROBE};
+    use magic_converter::video::{
+        convert_mts_files_in_directory_with_options, convert_segments_with_options, BatchOptions, Conversion,
+        ConversionOptions, FileOutcome, OverwritePolicy,
+    };
+    use std::fs;
+    use tempfile::tempdir;
+
+    // Tells the segments apart by name
+    fn tools() {
+        stand_in_tools_with_probes(&[
+            ("*00001*", Some(SECOND_SEGMENT_PROBE)),
+            ("*00002*", Some(NEW_RECORDING_PROBE)),
+            ("*", Some(FIRST_SEGMENT_PROBE)),
+        ]);
+    }
+
+    fn card() -> tempfile::TempDir {
+        let dir = tempdir().unwrap();
+        for name in ["00000.MTS", "00001.MTS", "00002.MTS"] {
+            create_input(dir.path(), name);
+        }
+        dir
+    }
+
+    #[test]
+    fn test_split_recording_is_joined() {
+        tools();
+        let input_dir = card();
+        let output_dir = tempdir().unwrap();
+
+        let report = convert_mts_files_in_directory_with_options(
+            input_dir.path(),
+            Some(output_dir.path()),
+            &BatchOptions::default(),
+            None,
+        )
+        .unwrap();
+        let inputs: Vec<_> = report.entries.iter().map(|entry| entry.input.file_name().unwrap().to_owned()).collect();
+        assert_eq!(inputs, ["00000.MTS", "00002.MTS"]);
+        assert_eq!(report.entries[0].joined, [input_dir.path().join("00001.MTS")]);
+        assert!(report.entries[1].joined.is_empty());
+        assert_eq!(dir_entries(output_dir.path()), [".magic-converter-journal.json", "00000.mp4", "00002.mp4"]);
+    }
+
+    #[test]
+    fn test_resume_reconverts_a_recording_whose_later_segment_changed() {
+        tools();
+        let input_dir = card();
+        let output_dir = tempdir().unwrap();
+        let conversion = ConversionOptions { overwrite: OverwritePolicy::Overwrite, ..ConversionOptions::default() };
+        let options = BatchOptions { conversion, resume: true, ..BatchOptions::default() };
+
+        convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None)
+            .unwrap();
+        fs::write(input_dir.path().join("00001.MTS"), "HDMV and more").unwrap();
+
+        let report =
+            convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None)
+                .unwrap();
+        assert_eq!((report.converted(), report.skipped()), (1, 1));
+        assert!(matches!(report.entries[0].outcome, FileOutcome::Converted { .. }));
+    }
+
+    #[test]
+    fn test_segments_kept_separate() {
+        tools();
+        let input_dir = card();
+        let output_dir = tempdir().unwrap();
+        let options = BatchOptions { join_segments: false, ..BatchOptions::default() };
+
+        let report =
+            convert_mts_files_in_directory_with_options(input_dir.path(), Some(output_dir.path()), &options, None)
+                .unwrap();
+        assert_eq!(report.entries.len(), 3);
+        assert!(report.entries.iter().all(|entry| entry.joined.is_empty()));
+    }
+
+    #[test]
+    fn test_segments_go_through_the_concat_demuxer() {
+        tools();
+        let dir = tempdir().unwrap();
+        let segments = [create_input(dir.path(), "args_a.mts"), create_input(dir.path(), "args_b.mts")];
+
+        let result = convert_segments_with_options(&segments, None, &ConversionOptions::default(), None);
+        let Ok(Conversion::Converted { output, .. }) = result else {
+            panic!("Expected a conversion, got {:?}", result);
+        };
+        assert_eq!(output, dir.path().join("args_a.mp4"));
+        let args = fs::read_to_string(&output).unwrap();
+        let args: Vec<&str> = args.lines().collect();
+        assert!(args.windows(5).any(|w| w == ["-f", "concat", "-safe", "0", "-i"]), "{:?}", args);
+        assert_eq!(dir_entries(dir.path()), ["args_a.mp4", "args_a.mts", "args_b.mts"], "the list is removed");
+    }
+}
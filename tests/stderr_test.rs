@@ -0,0 +1,76 @@
+// Runs the converter against a stand-in ffmpeg script, so these tests need a POSIX shell.
+#![cfg(unix)]
+
+mod common;
+
+use common::{create_input, stand_in_tools, NOISE_LINES};
+use magic_converter::video::{convert_mts_to_mp4_with_options, Conversion, ConversionOptions, ConvertError};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tempfile::tempdir;
+
+// Fails the test instead of hanging it if the conversion deadlocks
+fn convert_with_timeout(
+    input: PathBuf,
+    output_dir: PathBuf,
+    options: ConversionOptions,
+) -> Result<Conversion, ConvertError> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(convert_mts_to_mp4_with_options(&input, Some(&output_dir), &options, None));
+    });
+    rx.recv_timeout(Duration::from_secs(60))
+        .expect("conversion deadlocked while ffmpeg was writing to stderr")
+}
+
+#[test]
+fn test_large_stderr_output_does_not_deadlock() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "noisy.mts");
+
+    let result = convert_with_timeout(input, dir.path().to_path_buf(), ConversionOptions::default());
+    assert!(result.is_ok(), "Conversion should succeed, got {:?}", result);
+    assert!(dir.path().join("noisy.mp4").exists());
+}
+
+#[test]
+fn test_failure_reports_stderr_tail() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "broken.mts");
+
+    match convert_with_timeout(input.clone(), dir.path().to_path_buf(), ConversionOptions::default()) {
+        Err(error @ ConvertError::FfmpegFailed { .. }) => {
+            let tail = error.stderr_tail().unwrap();
+            assert!(tail.ends_with("Invalid data found when processing input"));
+            assert!(tail.lines().count() <= 20, "Only the tail of stderr should be kept");
+            assert!(error.to_string().contains("exited with code 1"));
+        }
+        other => panic!("Expected FfmpegFailed, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_stderr_is_streamed_to_log_sink() {
+    stand_in_tools();
+    let dir = tempdir().unwrap();
+    let input = create_input(dir.path(), "noisy-logged.mts");
+
+    let lines = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&lines);
+    let options = ConversionOptions {
+        stderr_sink: Some(Arc::new(move |line: &str| {
+            assert!(line.contains("noise line"));
+            counter.fetch_add(1, Ordering::SeqCst);
+        })),
+        ..ConversionOptions::default()
+    };
+
+    convert_with_timeout(input, dir.path().to_path_buf(), options).unwrap();
+    assert_eq!(lines.load(Ordering::SeqCst), NOISE_LINES);
+}
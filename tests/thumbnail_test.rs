@@ -0,0 +1,135 @@
+mod common;
+
+use magic_converter::video::{ImageFormat, ThumbnailTime};
+use std::time::Duration;
+
+const CLIP_PROBE: &str = r#"{ "streams": [{ "index": 0, "codec_type": "video", "codec_name": "h264" }],
+    "format": { "format_name": "mpegts", "duration": "100.0" } }"#;
+
+#[test]
+fn test_parse_thumbnail_time() {
+    assert_eq!("25%".parse::<ThumbnailTime>(), Ok(ThumbnailTime::Percent(25.0)));
+    assert_eq!("12.5".parse::<ThumbnailTime>(), Ok(ThumbnailTime::At(Duration::from_millis(12500))));
+    assert_eq!("01:30".parse::<ThumbnailTime>(), Ok(ThumbnailTime::At(Duration::from_secs(90))));
+    assert_eq!("1:02:03".parse::<ThumbnailTime>(), Ok(ThumbnailTime::At(Duration::from_secs(3723))));
+    assert!("150%".parse::<ThumbnailTime>().is_err());
+    assert!("-5".parse::<ThumbnailTime>().is_err());
+    assert!("soon".parse::<ThumbnailTime>().is_err());
+}
+
+#[test]
+fn test_parse_image_format() {
+    assert_eq!("jpg".parse::<ImageFormat>(), Ok(ImageFormat::Jpeg));
+    assert_eq!("WebP".parse::<ImageFormat>(), Ok(ImageFormat::Webp));
+    assert!("gif".parse::<ImageFormat>().is_err());
+}
+
+// Runs against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, dir_entries, stand_in_tools_with_probe};
+    use super::CLIP_PROBE;
+    use magic_converter::video::{
+        convert_mts_to_mp4_with_options, create_thumbnails, create_thumbnails_in_directory, BatchOptions,
+        ConversionOptions, ImageFormat, OverwritePolicy, ThumbnailOptions, ThumbnailTime,
+    };
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn args(path: &Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap().lines().map(String::from).collect()
+    }
+
+    fn has(args: &[String], expected: &[&str]) -> bool {
+        args.windows(expected.len()).any(|window| window == expected)
+    }
+
+    #[test]
+    fn test_poster_and_contact_sheet() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let options = ThumbnailOptions { contact_sheet: Some(9), ..ThumbnailOptions::default() };
+
+        let thumbnails = create_thumbnails(&input, None, &options).unwrap();
+        let poster = thumbnails.poster.unwrap();
+        assert_eq!(poster, dir.path().join("args.jpg"));
+        let poster_args = args(&poster);
+        assert!(has(&poster_args, &["-ss", "10.000", "-i"]), "10% into the clip: {:?}", poster_args);
+        assert!(has(&poster_args, &["-c:v", "mjpeg"]), "{:?}", poster_args);
+        assert!(has(&poster_args, &["-f", "image2", "-update", "1"]), "{:?}", poster_args);
+
+        let sheet = thumbnails.contact_sheet.unwrap();
+        assert_eq!(sheet, dir.path().join("args_sheet.jpg"));
+        let sheet_args = args(&sheet);
+        assert!(has(&sheet_args, &["-ss", "5.556"]), "{:?}", sheet_args);
+        assert!(has(&sheet_args, &["-filter:v", "fps=9/100,scale=320:-2,tile=3x3"]), "{:?}", sheet_args);
+        assert_eq!(dir_entries(dir.path()), ["args.jpg", "args.mts", "args_sheet.jpg"]);
+    }
+
+    #[test]
+    fn test_time_past_the_end_stays_in_the_clip() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let options = ThumbnailOptions {
+            time: ThumbnailTime::At(Duration::from_secs(600)),
+            format: ImageFormat::Webp,
+            width: Some(640),
+            ..ThumbnailOptions::default()
+        };
+
+        let poster = create_thumbnails(&input, None, &options).unwrap().poster.unwrap();
+        assert_eq!(poster.extension().unwrap(), "webp");
+        let poster_args = args(&poster);
+        assert!(has(&poster_args, &["-ss", "90.000"]), "{:?}", poster_args);
+        assert!(has(&poster_args, &["-filter:v", "scale=640:-2", "-c:v", "libwebp"]), "{:?}", poster_args);
+    }
+
+    #[test]
+    fn test_existing_poster_is_skipped() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "clip.mts");
+        fs::write(dir.path().join("clip.jpg"), "keep").unwrap();
+        let options = ThumbnailOptions { overwrite: OverwritePolicy::Skip, ..ThumbnailOptions::default() };
+
+        let thumbnails = create_thumbnails(&input, None, &options).unwrap();
+        assert_eq!(thumbnails.poster, None);
+        assert_eq!(fs::read_to_string(dir.path().join("clip.jpg")).unwrap(), "keep");
+    }
+
+    #[test]
+    fn test_directory_thumbnails() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let input_dir = tempdir().unwrap();
+        let output_dir = tempdir().unwrap();
+        create_input(input_dir.path(), "00001.MTS");
+        create_input(input_dir.path(), "00002.MTS");
+        create_input(input_dir.path(), "notes.txt");
+
+        let entries = create_thumbnails_in_directory(
+            input_dir.path(),
+            Some(output_dir.path()),
+            &BatchOptions::default(),
+            &ThumbnailOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.result.is_ok()));
+        assert_eq!(dir_entries(output_dir.path()), ["00001.jpg", "00002.jpg"]);
+    }
+
+    #[test]
+    fn test_thumbnails_alongside_conversion() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "clip.mts");
+        let options = ConversionOptions { thumbnails: Some(ThumbnailOptions::default()), ..ConversionOptions::default() };
+
+        convert_mts_to_mp4_with_options(&input, None, &options, None).unwrap();
+        assert_eq!(dir_entries(dir.path()), ["clip.jpg", "clip.mp4", "clip.mts"]);
+    }
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use magic_converter::video::trim::{format_timestamp, parse_timestamp};
+use magic_converter::video::TimeRange;
+use std::time::Duration;
+
+const CLIP_PROBE: &str = r#"{ "streams": [
+    { "index": 0, "codec_type": "video", "codec_name": "h264" },
+    { "index": 1, "codec_type": "audio", "codec_name": "aac" }
+], "format": { "format_name": "mpegts", "duration": "100.0" } }"#;
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("12.5"), Ok(Duration::from_millis(12500)));
+    assert_eq!(parse_timestamp("01:30"), Ok(Duration::from_secs(90)));
+    assert_eq!(parse_timestamp("1:02:03.5"), Ok(Duration::from_millis(3723500)));
+    assert!(parse_timestamp("1:2:3:4").is_err());
+    assert!(parse_timestamp("-1").is_err());
+    assert_eq!(format_timestamp(Duration::from_millis(3723500)), "01:02:03.500");
+}
+
+#[test]
+fn test_time_range() {
+    let range = TimeRange::with_duration(Some(Duration::from_secs(10)), Duration::from_secs(5)).unwrap();
+    assert_eq!(range.end, Some(Duration::from_secs(15)));
+    assert_eq!(range.to_string(), "00:00:10.000 to 00:00:15.000");
+    assert!(TimeRange::new(Some(Duration::from_secs(10)), Some(Duration::from_secs(10))).is_err());
+    assert!(TimeRange::with_duration(None, Duration::ZERO).is_err());
+    assert_eq!(TimeRange::new(Some(Duration::from_secs(10)), None).unwrap().to_string(), "00:00:10.000 to the end");
+}
+
+// Runs the converter against stand-in ffmpeg and ffprobe scripts, so these need a POSIX shell
+#[cfg(unix)]
+mod stand_in {
+    use super::common::{create_input, stand_in_tools_with_probe};
+    use super::CLIP_PROBE;
+    use magic_converter::video::{
+        convert_mts_to_mp4_with_options, Conversion, ConversionOptions, ConvertError, EncodingSettings, TimeRange,
+        VideoSettings,
+    };
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    fn converted_args(options: ConversionOptions) -> Vec<String> {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+        let Ok(Conversion::Converted { output, .. }) = result else {
+            panic!("Expected a conversion, got {:?}", result);
+        };
+        fs::read_to_string(output).unwrap().lines().map(String::from).collect()
+    }
+
+    fn has(args: &[String], expected: &[&str]) -> bool {
+        args.windows(expected.len()).any(|window| window == expected)
+    }
+
+    fn trim(start: u64, end: u64) -> Option<TimeRange> {
+        Some(TimeRange::new(Some(Duration::from_secs(start)), Some(Duration::from_secs(end))).unwrap())
+    }
+
+    #[test]
+    fn test_copied_video_is_cut_at_keyframes() {
+        let args = converted_args(ConversionOptions { trim: trim(10, 15), ..ConversionOptions::default() });
+        assert!(has(&args, &["-ss", "10.000", "-i"]), "seeks before the input: {:?}", args);
+        assert!(has(&args, &["-t", "5.000"]), "{:?}", args);
+        assert!(has(&args, &["-avoid_negative_ts", "make_zero"]), "{:?}", args);
+    }
+
+    #[test]
+    fn test_transcoded_video_is_cut_at_the_exact_frame() {
+        let options = ConversionOptions {
+            trim: trim(10, 15),
+            encoding: EncodingSettings { video: Some(VideoSettings::default()), audio: None },
+            ..ConversionOptions::default()
+        };
+        let args = converted_args(options);
+        assert!(has(&args, &["-ss", "10.000", "-i"]), "{:?}", args);
+        assert!(has(&args, &["-t", "5.000"]), "{:?}", args);
+        assert!(!args.iter().any(|arg| arg == "-avoid_negative_ts"), "{:?}", args);
+    }
+
+    #[test]
+    fn test_open_ended_trim_has_no_length() {
+        let trim = Some(TimeRange::new(Some(Duration::from_secs(90)), None).unwrap());
+        let args = converted_args(ConversionOptions { trim, ..ConversionOptions::default() });
+        assert!(has(&args, &["-ss", "90.000"]), "{:?}", args);
+        assert!(!args.iter().any(|arg| arg == "-t"), "{:?}", args);
+    }
+
+    #[test]
+    fn test_start_after_the_end_of_the_clip() {
+        stand_in_tools_with_probe(CLIP_PROBE);
+        let dir = tempdir().unwrap();
+        let input = create_input(dir.path(), "args.mts");
+        let options = ConversionOptions { trim: trim(120, 130), ..ConversionOptions::default() };
+
+        let result = convert_mts_to_mp4_with_options(&input, Some(dir.path()), &options, None);
+        assert!(matches!(result, Err(ConvertError::TrimOutOfRange { .. })), "got {:?}", result);
+        assert!(!dir.path().join("args.mp4").exists());
+    }
+}
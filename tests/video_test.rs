@@ -1,9 +1,9 @@
-use magic_converter::video::{convert_mts_files_in_directory, convert_mts_to_mp4};
+use magic_converter::video::{convert_mts_files_in_directory, convert_mts_to_mp4, ConvertError};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tempfile::{tempdir, TempDir};
 use std::process::Command;
 
@@ -63,10 +63,10 @@ fn test_empty_directory() {
 fn test_no_mts_files() {
     let setup = TestSetup::new();
     
-    // Create various non-MTS files
+    // Create various files that are not convertible videos
     setup.create_non_mts_file("test1", "txt");
     setup.create_non_mts_file("test2", "mp4");
-    setup.create_non_mts_file("test3", "avi");
+    setup.create_non_mts_file("test3", "jpg");
 
     let result = convert_mts_files_in_directory(
         setup.input_dir.path(),
@@ -91,9 +91,13 @@ fn test_progress_callback() {
 
     let progress_counter = Arc::new(AtomicUsize::new(0));
     let progress_clone = Arc::clone(&progress_counter);
-    
+    let last_progress = Arc::new(Mutex::new(0.0f32));
+
     let callback = Arc::new(move |progress: f32| {
-        assert!(progress >= 0.0 && progress <= 1.0, "Progress should be between 0 and 1");
+        assert!((0.0..=1.0).contains(&progress), "Progress should be between 0 and 1");
+        let mut last = last_progress.lock().unwrap();
+        assert!(progress >= *last, "Progress should never move backwards");
+        *last = progress;
         progress_clone.fetch_add(1, Ordering::SeqCst);
     });
 
@@ -121,6 +125,22 @@ fn test_invalid_input_path() {
         None
     );
     assert!(result.is_err(), "Should error on invalid input file");
+    match result.unwrap_err() {
+        ConvertError::InputNotFound(path) => assert_eq!(path, invalid_path),
+        other => panic!("Expected InputNotFound, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_missing_input_directory() {
+    let setup = TestSetup::new();
+    let missing_dir = setup.input_dir.path().join("missing");
+
+    let result = convert_mts_files_in_directory(&missing_dir, Some(setup.output_dir.path()), None);
+    match result {
+        Err(ConvertError::Io { path, .. }) => assert_eq!(path, missing_dir),
+        other => panic!("Expected an I/O error for the directory, got {:?}", other),
+    }
 }
 
 // Integration test that only runs if ffmpeg is available
@@ -142,4 +162,10 @@ fn test_actual_conversion() {
     
     // Since our dummy MTS file isn't actually valid, we expect an error
     assert!(result.is_err(), "Should fail with invalid MTS content");
+    let error = result.unwrap_err();
+    assert!(
+        matches!(error, ConvertError::FfmpegFailed { .. } | ConvertError::UnsupportedCodec { .. }),
+        "Expected ffmpeg to reject the input, got {:?}", error
+    );
+    assert!(error.stderr_tail().is_some_and(|tail| !tail.is_empty()), "ffmpeg's stderr should be captured");
 }